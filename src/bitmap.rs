@@ -1,31 +1,116 @@
 //! renderer agnostic bitmap representation
 
+use image::{ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage, imageops::FilterType};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::{self, SeqAccess, Visitor}};
 use std::{
     fmt,
     str::FromStr,
 };
 
-/// rgb representation of a color
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// rgba representation of a color. colors aren't premultiplied, so the other channels are kept as-is whatever the alpha is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Color {
     pub red: u8,
     pub green: u8,
     pub blue: u8,
+    pub alpha: u8,
 }
 
 impl Color {
-    /// lets us create a color with less typing
+    /// fully transparent color
+    pub const TRANSPARENT: Self = Self::with_alpha(0, 0, 0, 0);
+
+    /// lets us create an opaque color with less typing
     pub const fn new(red: u8, green: u8, blue: u8) -> Self {
-        Self { red, green, blue }
+        Self { red, green, blue, alpha: 255 }
+    }
+
+    /// creates a color that may be see-through
+    pub const fn with_alpha(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Self { red, green, blue, alpha }
+    }
+
+    /// composites this color over another one with the Porter-Duff over operator
+    pub fn over(self, under: Self) -> Self {
+        match self.alpha {
+            255 => return self,
+            0 => return under,
+            _ => (),
+        }
+
+        let (over_alpha, under_alpha) = (self.alpha as u32, under.alpha as u32);
+
+        // alpha of the result, scaled up by 255 so we don't lose precision
+        let alpha = over_alpha * 255 + under_alpha * (255 - over_alpha);
+
+        let blend = |over: u8, under: u8| {
+            ((over as u32 * over_alpha * 255 + under as u32 * under_alpha * (255 - over_alpha) + alpha / 2) / alpha) as u8
+        };
+
+        Self::with_alpha(
+            blend(self.red, under.red),
+            blend(self.green, under.green),
+            blend(self.blue, under.blue),
+            ((alpha + 127) / 255) as u8,
+        )
     }
 }
 
 impl From<[u8; 3]> for Color {
     fn from(f: [u8; 3]) -> Self {
-        Self {
-            red: f[0],
-            green: f[1],
-            blue: f[2],
+        Self::new(f[0], f[1], f[2])
+    }
+}
+
+impl From<[u8; 4]> for Color {
+    fn from(f: [u8; 4]) -> Self {
+        Self::with_alpha(f[0], f[1], f[2], f[3])
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor;
+
+        impl<'de> Visitor<'de> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an array of 3 or 4 color components")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut components = [0, 0, 0, 255];
+                let mut len = 0;
+
+                while let Some(component) = seq.next_element::<u8>()? {
+                    if len == 4 {
+                        return Err(de::Error::invalid_length(len + 1, &self));
+                    }
+
+                    components[len] = component;
+                    len += 1;
+                }
+
+                if len < 3 {
+                    return Err(de::Error::invalid_length(len, &self));
+                }
+
+                Ok(components.into())
+            }
+        }
+
+        deserializer.deserialize_seq(ColorVisitor)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // alpha is left off opaque colors
+        if self.alpha == 255 {
+            [self.red, self.green, self.blue].serialize(serializer)
+        } else {
+            [self.red, self.green, self.blue, self.alpha].serialize(serializer)
         }
     }
 }
@@ -34,30 +119,24 @@ impl FromStr for Color {
     type Err = String;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        // get iterator over string
-        let mut chars = input.chars();
-
-        // get first character
-        if let Some(c) = chars.next() {
-            if c != '#' { // make sure it's valid
-                Err("invalid color".to_string())
-            } else {
-                // parse rest of string as a number in hex notation
-                let num = usize::from_str_radix(chars.as_str(), 16).map_err(|err| err.to_string())?;
-        
-                // extract red, green, blue components from number
-                if num > 0xffffff {
-                    Err("number too big".to_string())
-                } else {
-                    Ok(Self {
-                        red: ((num >> 16) & 0xff) as u8,
-                        green: ((num >> 8) & 0xff) as u8,
-                        blue: (num & 0xff) as u8,
-                    })
-                }
-            }
-        } else {
-            Err("invalid color".to_string())
+        if input == "transparent" {
+            return Ok(Self::TRANSPARENT);
+        }
+
+        // make sure it's valid
+        let digits = match input.strip_prefix('#') {
+            Some(digits) => digits,
+            None => return Err("invalid color".to_string()),
+        };
+
+        // parse rest of string as a number in hex notation
+        let num = u32::from_str_radix(digits, 16).map_err(|err| err.to_string())?;
+
+        // extract red, green, blue and maybe alpha components from number
+        match digits.len() {
+            6 => Ok(Self::new((num >> 16) as u8, (num >> 8) as u8, num as u8)),
+            8 => Ok(Self::with_alpha((num >> 24) as u8, (num >> 16) as u8, (num >> 8) as u8, num as u8)),
+            _ => Err("colors must have 6 or 8 digits".to_string()),
         }
     }
 }
@@ -68,7 +147,157 @@ impl fmt::Display for Color {
             ((self.red as usize) << 16) |
             ((self.green as usize) << 8) |
             (self.blue as usize);
-        write!(f, "#{:06x}", num)
+
+        // alpha is left off opaque colors
+        if self.alpha == 255 {
+            write!(f, "#{:06x}", num)
+        } else {
+            write!(f, "#{:06x}{:02x}", num, self.alpha)
+        }
+    }
+}
+
+/// how to decide which parts of a self-intersecting polygon are inside it
+#[derive(Debug, Copy, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillRule {
+    /// inside if the edges wind around the point any number of times
+    #[default]
+    NonZero,
+
+    /// inside if a line out from the point crosses an odd number of edges
+    EvenOdd,
+}
+
+/// color space colors are blended in when mixing them together
+#[derive(Debug, Copy, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    /// blends the sRGB values directly, which is what most image editors and browsers do
+    #[default]
+    Srgb,
+
+    /// blends light intensity, undoing sRGB gamma first. avoids dark bands in the middle of blends
+    Linear,
+
+    /// blends in the OKLab perceptual color space, keeping lightness and hue changes even
+    Oklab,
+}
+
+impl ColorSpace {
+    /// mixes two colors together, going from `a` at 0 to `b` at 1
+    pub fn mix(&self, a: Color, b: Color, t: f64) -> Color {
+        let (a_alpha, b_alpha) = (a.alpha as f64 / 255.0, b.alpha as f64 / 255.0);
+        let alpha = a_alpha + (b_alpha - a_alpha) * t;
+
+        // colors are weighted by their alpha, so transparent colors don't tint their neighbours
+        let lerp = |a: [f64; 3], b: [f64; 3]| [0, 1, 2].map(|i| {
+            let mixed = a[i] * a_alpha + (b[i] * b_alpha - a[i] * a_alpha) * t;
+            if alpha > 0.0 { mixed / alpha } else { 0.0 }
+        });
+
+        let mixed = match self {
+            Self::Srgb => lerp(to_float(a), to_float(b)),
+            Self::Linear => lerp(to_float(a).map(to_linear), to_float(b).map(to_linear)).map(from_linear),
+            Self::Oklab => from_oklab(lerp(to_oklab(a), to_oklab(b))).map(from_linear),
+        };
+
+        let Color { red, green, blue, .. } = from_float(mixed);
+        Color::with_alpha(red, green, blue, (alpha * 255.0).round() as u8)
+    }
+}
+
+/// converts a color into sRGB values between 0 and 1
+fn to_float(color: Color) -> [f64; 3] {
+    [color.red, color.green, color.blue].map(|c| c as f64 / 255.0)
+}
+
+/// converts sRGB values between 0 and 1 into a color, clamping anything out of range
+fn from_float(color: [f64; 3]) -> Color {
+    let [red, green, blue] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    Color::new(red, green, blue)
+}
+
+/// undoes sRGB gamma
+fn to_linear(c: f64) -> f64 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// applies sRGB gamma
+fn from_linear(c: f64) -> f64 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.max(0.0).powf(1.0 / 2.4) - 0.055 }
+}
+
+/// converts a color into OKLab
+fn to_oklab(color: Color) -> [f64; 3] {
+    let [red, green, blue] = to_float(color).map(to_linear);
+
+    let l = (0.4122214708 * red + 0.5363325363 * green + 0.0514459929 * blue).cbrt();
+    let m = (0.2119034982 * red + 0.6806995451 * green + 0.1073969566 * blue).cbrt();
+    let s = (0.0883024619 * red + 0.2817188376 * green + 0.6299787005 * blue).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// converts OKLab into linear sRGB values
+fn from_oklab([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
+
+/// filter used to scale images
+#[derive(Debug, Copy, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFilter {
+    /// picks the closest pixel, keeping edges sharp. good for pixel art
+    Nearest,
+
+    /// blends the closest 2x2 pixels
+    Linear,
+
+    /// blends the closest 4x4 pixels with a Catmull-Rom curve
+    #[default]
+    Cubic,
+
+    /// blurs the image slightly as it's scaled
+    Gaussian,
+
+    /// sharpest and slowest of the smooth filters
+    Lanczos,
+}
+
+impl From<ImageFilter> for FilterType {
+    fn from(filter: ImageFilter) -> Self {
+        match filter {
+            ImageFilter::Nearest => Self::Nearest,
+            ImageFilter::Linear => Self::Triangle,
+            ImageFilter::Cubic => Self::CatmullRom,
+            ImageFilter::Gaussian => Self::Gaussian,
+            ImageFilter::Lanczos => Self::Lanczos3,
+        }
+    }
+}
+
+/// anything that can decide the color of a point, used to fill in shapes
+pub trait Paint {
+    /// gets the color at the given point
+    fn color_at(&self, x: f64, y: f64) -> Color;
+}
+
+impl Paint for Color {
+    fn color_at(&self, _x: f64, _y: f64) -> Color {
+        *self
     }
 }
 
@@ -88,7 +317,7 @@ impl Bitmap {
     /// create a new bitmap of the provided size
     pub fn new(width: usize, height: usize) -> Self {
         Self {
-            data: vec![vec![Color::new(0, 0, 0); width]; height],
+            data: vec![vec![Color::TRANSPARENT; width]; height],
             width, height,
         }
     }
@@ -100,6 +329,13 @@ impl Bitmap {
         }
     }
 
+    /// paint a color over a cell in the bitmap, blending it with what's already there
+    pub fn blend(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.data[y][x] = color.over(self.data[y][x]);
+        }
+    }
+
     /// get a cell in the bitmap
     pub fn get(&self, x: usize, y: usize) -> Option<Color> {
         if x < self.width && y < self.height {
@@ -109,8 +345,24 @@ impl Bitmap {
         }
     }
 
+    /// converts this bitmap to an image of the given size with an alpha channel, leaving anything outside the bitmap transparent
+    pub fn to_rgba_image(&self, width: u32, height: u32) -> RgbaImage {
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let pixel = self.get(x as usize, y as usize).unwrap_or(Color::TRANSPARENT);
+            Rgba([pixel.red, pixel.green, pixel.blue, pixel.alpha])
+        })
+    }
+
+    /// converts this bitmap to an image of the given size, filling anything outside the bitmap with black
+    pub fn to_rgb_image(&self, width: u32, height: u32) -> RgbImage {
+        ImageBuffer::from_fn(width, height, |x, y| {
+            let pixel = self.get(x as usize, y as usize).unwrap_or(Color::new(0, 0, 0));
+            Rgb([pixel.red, pixel.green, pixel.blue])
+        })
+    }
+
     /// draw a rectangle
-    pub fn draw_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+    pub fn draw_rect(&mut self, x: usize, y: usize, width: usize, height: usize, paint: &impl Paint) {
         // make sure we're not out of bounds
         if x >= self.width || y >= self.height {
             return;
@@ -118,8 +370,268 @@ impl Bitmap {
 
         for y2 in y..y + height {
             for x2 in x..x + width {
-                self.set(x2, y2, color);
+                self.blend(x2, y2, paint.color_at(x2 as f64 + 0.5, y2 as f64 + 0.5));
+            }
+        }
+    }
+
+    /// draw an image stretched over a rectangle with its top left corner at the given position, blending it in with
+    /// its alpha channel. each pixel takes the color of the closest pixel in the image
+    pub fn draw_image(&mut self, x: isize, y: isize, width: usize, height: usize, image: &RgbaImage) {
+        if width == 0 || height == 0 || image.width() == 0 || image.height() == 0 {
+            return;
+        }
+
+        // only look at the part of the rectangle that's inside the bitmap
+        let clip = |start: isize, size: usize, max: usize| (start.max(0) as usize, (start + size as isize).clamp(0, max as isize) as usize);
+        let (start_x, end_x) = clip(x, width, self.width);
+        let (start_y, end_y) = clip(y, height, self.height);
+
+        for y2 in start_y..end_y {
+            let image_y = (y2 as isize - y) as usize * image.height() as usize / height;
+
+            for x2 in start_x..end_x {
+                let image_x = (x2 as isize - x) as usize * image.width() as usize / width;
+
+                let [red, green, blue, alpha] = image.get_pixel(image_x as u32, image_y as u32).0;
+                self.blend(x2, y2, Color::with_alpha(red, green, blue, alpha));
+            }
+        }
+    }
+
+    /// shrink the bitmap by the given factor, averaging each block of pixels into one
+    pub fn downsample(&self, factor: usize) -> Self {
+        let mut bitmap = Self::new(self.width / factor, self.height / factor);
+        let count = factor * factor;
+
+        for y in 0..bitmap.height {
+            for x in 0..bitmap.width {
+                let mut sum = [0usize; 4];
+
+                // colors are weighted by their alpha, so transparent pixels don't darken the edges of shapes
+                for row in self.data[y * factor..(y + 1) * factor].iter() {
+                    for color in row[x * factor..(x + 1) * factor].iter() {
+                        let alpha = color.alpha as usize;
+                        sum[0] += color.red as usize * alpha;
+                        sum[1] += color.green as usize * alpha;
+                        sum[2] += color.blue as usize * alpha;
+                        sum[3] += alpha;
+                    }
+                }
+
+                // round to the closest value rather than always down
+                let alpha = sum[3];
+                let channel = |sum: usize| (sum + alpha / 2).checked_div(alpha).unwrap_or(0) as u8;
+                bitmap.data[y][x] = Color::with_alpha(channel(sum[0]), channel(sum[1]), channel(sum[2]), ((alpha + count / 2) / count) as u8);
+            }
+        }
+
+        bitmap
+    }
+
+    /// fill a polygon using the given fill rule. a pixel is filled if its center lies inside the polygon,
+    /// so polygons that share an edge never overlap or leave a gap between them
+    pub fn fill_polygon(&mut self, points: &[(f64, f64)], rule: FillRule, paint: &impl Paint) {
+        if points.len() < 3 {
+            return;
+        }
+
+        // only look at rows the polygon could cover
+        let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+        let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+        let start = (min_y - 0.5).ceil().max(0.0) as usize;
+        let end = ((max_y - 0.5).ceil().max(0.0) as usize).min(self.height);
+
+        // where edges cross the current row, along with which direction they're going
+        let mut crossings: Vec<(f64, i32)> = Vec::new();
+
+        for y in start..end {
+            let center = y as f64 + 0.5;
+
+            crossings.clear();
+
+            for (i, (x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+
+                // edges include their top end but not their bottom end, so shared vertices are only counted once
+                let direction = if *y0 <= center && y1 > center {
+                    1
+                } else if y1 <= center && *y0 > center {
+                    -1
+                } else {
+                    continue;
+                };
+
+                // crossings are worked out from the top end of the edge, so an edge shared by two polygons crosses
+                // in exactly the same place for both of them whichever way they go around it
+                let ((top_x, top_y), (bottom_x, bottom_y)) = if *y0 <= y1 { ((*x0, *y0), (x1, y1)) } else { ((x1, y1), (*x0, *y0)) };
+
+                crossings.push((top_x + (center - top_y) / (bottom_y - top_y) * (bottom_x - top_x), direction));
+            }
+
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            // fill spans that are inside according to the fill rule
+            let mut winding = 0;
+
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+
+                let inside = match rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+
+                if inside {
+                    let from = (pair[0].0 - 0.5).ceil().max(0.0) as usize;
+                    let to = ((pair[1].0 - 0.5).ceil().max(0.0) as usize).min(self.width);
+
+                    for x in from..to {
+                        self.blend(x, y, paint.color_at(x as f64 + 0.5, center));
+                    }
+                }
+            }
+        }
+    }
+
+    /// fill an ellipse. like with polygons, a pixel is filled if its center lies inside the ellipse
+    pub fn fill_ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64, paint: &impl Paint) {
+        self.fill_between_ellipses(cx, cy, (rx, ry), (0.0, 0.0), paint);
+    }
+
+    /// fill the band between two ellipses, centered on the given radii and `width` apart
+    pub fn fill_ring(&mut self, cx: f64, cy: f64, rx: f64, ry: f64, width: f64, paint: &impl Paint) {
+        self.fill_between_ellipses(cx, cy, (rx + width / 2.0, ry + width / 2.0), (rx - width / 2.0, ry - width / 2.0), paint);
+    }
+
+    /// fill everything inside the outer ellipse that isn't inside the inner one
+    fn fill_between_ellipses(&mut self, cx: f64, cy: f64, outer: (f64, f64), inner: (f64, f64), paint: &impl Paint) {
+        // checks whether a point is inside an ellipse with the given radii
+        let inside = |x: f64, y: f64, (rx, ry): (f64, f64)| rx > 0.0 && ry > 0.0 && (x / rx).powi(2) + (y / ry).powi(2) <= 1.0;
+
+        if outer.0 <= 0.0 || outer.1 <= 0.0 {
+            return;
+        }
+
+        // only look at pixels the outer ellipse could cover
+        let start_x = (cx - outer.0 - 0.5).floor().max(0.0) as usize;
+        let end_x = ((cx + outer.0 + 0.5).ceil().max(0.0) as usize).min(self.width);
+        let start_y = (cy - outer.1 - 0.5).floor().max(0.0) as usize;
+        let end_y = ((cy + outer.1 + 0.5).ceil().max(0.0) as usize).min(self.height);
+
+        for y in start_y..end_y {
+            let dy = y as f64 + 0.5 - cy;
+
+            for x in start_x..end_x {
+                let dx = x as f64 + 0.5 - cx;
+
+                if inside(dx, dy, outer) && !inside(dx, dy, inner) {
+                    self.blend(x, y, paint.color_at(x as f64 + 0.5, y as f64 + 0.5));
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::new(255, 0, 0);
+
+    /// fills a polygon into a 100x100 bitmap
+    fn fill(points: &[(f64, f64)], rule: FillRule) -> Bitmap {
+        let mut bitmap = Bitmap::new(100, 100);
+        bitmap.fill_polygon(points, rule, &RED);
+        bitmap
+    }
+
+    /// a five pointed star drawn in one stroke, so its middle is wound around twice
+    fn pentagram() -> Vec<(f64, f64)> {
+        [0, 2, 4, 1, 3].iter()
+            .map(|i| (*i as f64 * 72.0 - 90.0).to_radians())
+            .map(|angle| (50.0 + angle.cos() * 50.0, 50.0 + angle.sin() * 50.0))
+            .collect()
+    }
+
+    /// a square with a square hole cut out of it, going around the hole in the given direction
+    fn square_with_hole(hole_clockwise: bool) -> Vec<(f64, f64)> {
+        let mut hole = vec![(30.0, 30.0), (70.0, 30.0), (70.0, 70.0), (30.0, 70.0)];
+        if !hole_clockwise {
+            hole[1..].reverse();
+        }
+
+        // the outside and the hole are joined by an edge going there and back, which cancels itself out
+        let mut points = vec![(10.0, 10.0), (90.0, 10.0), (90.0, 90.0), (10.0, 90.0), (10.0, 10.0)];
+        points.extend(hole);
+        points.push((30.0, 30.0));
+        points
+    }
+
+    #[test]
+    fn mixing_depends_on_color_space() {
+        let (black, white) = (Color::new(0, 0, 0), Color::new(255, 255, 255));
+
+        assert_eq!(ColorSpace::Srgb.mix(black, white, 0.5), Color::new(128, 128, 128));
+        assert_eq!(ColorSpace::Linear.mix(black, white, 0.5), Color::new(188, 188, 188));
+        assert_eq!(ColorSpace::Oklab.mix(black, white, 0.0), black);
+        assert_eq!(ColorSpace::Oklab.mix(black, white, 1.0), white);
+    }
+
+    #[test]
+    fn mixing_ignores_the_color_of_transparent_ends() {
+        let mixed = ColorSpace::Srgb.mix(Color::TRANSPARENT, RED, 0.5);
+
+        assert_eq!(mixed, Color::with_alpha(255, 0, 0, 128));
+    }
+
+    #[test]
+    fn nonzero_fills_overlapping_windings() {
+        let bitmap = fill(&pentagram(), FillRule::NonZero);
+
+        assert_eq!(bitmap.get(50, 50), Some(RED));
+        assert_eq!(bitmap.get(50, 5), Some(RED));
+    }
+
+    #[test]
+    fn evenodd_leaves_overlapping_windings_empty() {
+        let bitmap = fill(&pentagram(), FillRule::EvenOdd);
+
+        assert_eq!(bitmap.get(50, 50), Some(Color::TRANSPARENT));
+        assert_eq!(bitmap.get(50, 5), Some(RED));
+    }
+
+    #[test]
+    fn holes_depend_on_direction_with_nonzero() {
+        let same = fill(&square_with_hole(true), FillRule::NonZero);
+        let opposite = fill(&square_with_hole(false), FillRule::NonZero);
+
+        assert_eq!(same.get(50, 50), Some(RED));
+        assert_eq!(opposite.get(50, 50), Some(Color::TRANSPARENT));
+        assert_eq!(opposite.get(20, 50), Some(RED));
+    }
+
+    #[test]
+    fn holes_ignore_direction_with_evenodd() {
+        for hole_clockwise in [true, false] {
+            let bitmap = fill(&square_with_hole(hole_clockwise), FillRule::EvenOdd);
+
+            assert_eq!(bitmap.get(50, 50), Some(Color::TRANSPARENT));
+            assert_eq!(bitmap.get(20, 50), Some(RED));
+            assert_eq!(bitmap.get(5, 50), Some(Color::TRANSPARENT));
+        }
+    }
+
+    #[test]
+    fn polygons_sharing_an_edge_dont_overlap() {
+        let mut bitmap = Bitmap::new(100, 100);
+        let half = Color::with_alpha(255, 0, 0, 128);
+
+        // two triangles making up a square, split along the diagonal
+        bitmap.fill_polygon(&[(0.0, 0.0), (100.0, 0.0), (0.0, 100.0)], FillRule::NonZero, &half);
+        bitmap.fill_polygon(&[(100.0, 0.0), (100.0, 100.0), (0.0, 100.0)], FillRule::NonZero, &half);
+
+        assert!(bitmap.data.iter().flatten().all(|color| *color == half));
+    }
+}
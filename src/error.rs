@@ -0,0 +1,141 @@
+//! errors that can happen while loading and rendering flags
+
+use crate::format::FlagFormat;
+use crate::util::quoted_list;
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/// everything that can go wrong while loading or rendering a flag
+#[derive(Debug)]
+pub enum PrideError {
+    /// a number in the flag couldn't be understood
+    InvalidNumber {
+        /// what the number is for
+        what: &'static str,
+
+        /// the number as it was written
+        value: String,
+    },
+
+    /// there's no flag with the given name
+    UnknownFlag {
+        /// the name that was looked for
+        name: String,
+
+        /// names of flags that look like it, most similar first
+        suggestions: Vec<String>,
+    },
+
+    /// the flag file couldn't be read
+    ReadFlag {
+        /// where the flag was looked for
+        path: PathBuf,
+
+        /// why it couldn't be read
+        source: io::Error,
+    },
+
+    /// the flag isn't valid in the format it's written in, or doesn't describe a flag
+    Parse {
+        /// format the flag was read as
+        format: FlagFormat,
+
+        /// what was wrong with it
+        source: Box<dyn Error + Send + Sync>,
+    },
+
+    /// the flag couldn't be written out in another format
+    Convert {
+        /// format the flag was being written in
+        format: FlagFormat,
+
+        /// why it couldn't be written
+        source: Box<dyn Error + Send + Sync>,
+    },
+
+    /// the renderer's options couldn't be parsed
+    RendererOptions(serde_yaml::Error),
+
+    /// one of the renderer's options has a value it can't use
+    InvalidRendererOption(String),
+
+    /// an image used by the flag couldn't be loaded
+    LoadImage {
+        /// where the image was looked for
+        path: PathBuf,
+
+        /// why it couldn't be loaded
+        source: image::ImageError,
+    },
+
+    /// an image couldn't be encoded or saved
+    Image(image::ImageError),
+
+    /// reading or writing a file or the terminal failed
+    Io(io::Error),
+
+    /// the size of the terminal couldn't be found, usually because we're not running in one
+    TerminalSize(io::Error),
+
+    /// the framebuffer couldn't be opened or switched between text and graphics modes
+    #[cfg(all(target_os = "linux", feature = "framebuffer"))]
+    Framebuffer(framebuffer::FramebufferError),
+}
+
+impl fmt::Display for PrideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidNumber { what, value } => write!(f, "invalid {} \"{}\"", what, value),
+            Self::UnknownFlag { name, suggestions } if suggestions.is_empty() => write!(f, "no flag named \"{}\"", name),
+            Self::UnknownFlag { name, suggestions } => write!(f, "no flag named \"{}\", did you mean {}?", name, quoted_list(suggestions)),
+            Self::ReadFlag { path, source } => write!(f, "couldn't read flag {}: {}", path.display(), source),
+            Self::Parse { format, source } => write!(f, "invalid {} flag: {}", format, source),
+            Self::Convert { format, source } => write!(f, "couldn't write flag as {}: {}", format, source),
+            Self::RendererOptions(err) => write!(f, "invalid renderer options: {}", err),
+            Self::InvalidRendererOption(reason) => write!(f, "invalid renderer option: {}", reason),
+            Self::LoadImage { path, source } => write!(f, "couldn't load image {}: {}", path.display(), source),
+            Self::Image(err) => write!(f, "image error: {}", err),
+            Self::Io(err) => write!(f, "i/o error: {}", err),
+            Self::TerminalSize(err) => write!(f, "couldn't get the terminal size: {}", err),
+
+            #[cfg(all(target_os = "linux", feature = "framebuffer"))]
+            Self::Framebuffer(err) => write!(f, "framebuffer error: {}", err),
+        }
+    }
+}
+
+impl Error for PrideError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::ReadFlag { source, .. } => Some(source),
+            Self::Parse { source, .. } | Self::Convert { source, .. } => Some(source.as_ref()),
+            Self::RendererOptions(err) => Some(err),
+            Self::LoadImage { source, .. } => Some(source),
+            Self::Image(err) => Some(err),
+            Self::Io(err) | Self::TerminalSize(err) => Some(err),
+
+            #[cfg(all(target_os = "linux", feature = "framebuffer"))]
+            Self::Framebuffer(err) => Some(err),
+
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PrideError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<image::ImageError> for PrideError {
+    fn from(err: image::ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "framebuffer"))]
+impl From<framebuffer::FramebufferError> for PrideError {
+    fn from(err: framebuffer::FramebufferError) -> Self {
+        Self::Framebuffer(err)
+    }
+}
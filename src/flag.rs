@@ -1,22 +1,63 @@
 //! flag structure and rendering
 
-use serde::Deserialize;
-use crate::bitmap::{Bitmap, Color};
+use image::{RgbaImage, imageops};
+use serde::{Deserialize, Deserializer, Serialize, de::{MapAccess, SeqAccess, Visitor, value::{MapAccessDeserializer, SeqAccessDeserializer}}};
+use std::{fmt, fs, path::{Path, PathBuf}, sync::Arc};
+use crate::bitmap::{Bitmap, Color, ColorSpace, FillRule, ImageFilter, Paint};
+use crate::error::PrideError;
+use crate::format::FlagFormat;
 use crate::util::PartialSize;
-use crate::render::Renderer;
+use crate::render::{AnyRenderer, Renderer, VectorRenderer};
+use crate::shape::{Drawing, Fill, Gradient, GradientKind, Shape};
 
 /// our flag struct
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Flag {
+    /// name of flag- filled in with the name it was found by if it's looked up by name and this isn't set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// name of flag as it should be shown to people, like "transgender pride flag"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// other names the flag can be found by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+
+    /// what the flag stands for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// who designed the flag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub designer: Option<String>,
+
+    /// year the flag was designed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+
+    /// where to find out more about the flag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// tags the flag can be found by, like "gender" or "romantic"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
     /// aspect ratio of flag
     pub aspect: PartialSize,
 
     /// vec of horizontal sections
     pub sections: Vec<FlagSection>,
+
+    /// vec of shapes painted over the sections, from back to front
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shapes: Vec<FlagShape>,
 }
 
 /// horizontal section of flag
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FlagSection {
     /// width of flag section
     pub width: PartialSize,
@@ -26,7 +67,7 @@ pub struct FlagSection {
 }
 
 /// vertical section of flag
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FlagSubSection {
     /// width relative to parent section
     pub width: PartialSize,
@@ -35,47 +76,302 @@ pub struct FlagSubSection {
     pub height: PartialSize,
 
     /// color of section
-    pub color: [u8; 3],
+    pub color: FlagFill,
 }
 
-/// render the given flag with the given renderer
-pub fn render_flag(renderer: &mut Box<dyn Renderer>, flag: &Flag, background: Color) {
-    // get size we can render to
-    let (width, height) = renderer.get_size();
-    let aspect = width as f64 / height as f64;
+impl Flag {
+    /// parse a flag from YAML. images aren't loaded, since there's nowhere to load them from- see `load_images`
+    pub fn from_yaml(yaml: &str) -> Result<Self, PrideError> {
+        Self::parse_as(yaml, FlagFormat::Yaml)
+    }
+
+    /// parse a flag, guessing which format it's written in- see `FlagFormat::detect`
+    pub fn parse(text: &str) -> Result<Self, PrideError> {
+        Self::parse_as(text, FlagFormat::detect(text))
+    }
+
+    /// parse a flag written in the given format
+    pub fn parse_as(text: &str, format: FlagFormat) -> Result<Self, PrideError> {
+        let result = match format {
+            FlagFormat::Yaml => serde_yaml::from_str(text).map_err(Into::into),
+            FlagFormat::Json => serde_json::from_str(text).map_err(Into::into),
+            FlagFormat::Toml => toml::from_str(text).map_err(Into::into),
+        };
+
+        result.map_err(|source| PrideError::Parse { format, source })
+    }
+
+    /// write this flag out in the given format
+    pub fn to_string_as(&self, format: FlagFormat) -> Result<String, PrideError> {
+        let result = match format {
+            FlagFormat::Yaml => serde_yaml::to_string(self).map_err(Into::into),
+            FlagFormat::Json => serde_json::to_string_pretty(self).map_err(Into::into),
+            FlagFormat::Toml => toml::to_string(self).map_err(Into::into),
+        };
+
+        result.map_err(|source| PrideError::Convert { format, source })
+    }
+
+    /// load a flag from a file, loading its images from next to it- see `read`
+    pub fn load(path: &Path) -> Result<Self, PrideError> {
+        let mut flag = Self::read(path)?;
+        flag.load_images(path.parent().unwrap_or_else(|| Path::new("")))?;
+
+        Ok(flag)
+    }
+
+    /// read a flag from a file without loading its images. the file's format is picked by its extension, or guessed
+    /// from what's in it if that isn't known
+    pub fn read(path: &Path) -> Result<Self, PrideError> {
+        let text = fs::read_to_string(path).map_err(|source| PrideError::ReadFlag { path: path.to_path_buf(), source })?;
+        let format = FlagFormat::from_path(path).unwrap_or_else(|| FlagFormat::detect(&text));
+
+        Self::parse_as(&text, format)
+    }
+
+    /// rasterize this flag to a bitmap of the given size- see `rasterize_flag`
+    pub fn rasterize(&self, width: usize, height: usize, background: Color, samples: usize) -> Result<Bitmap, PrideError> {
+        rasterize_flag(self, width, height, background, samples)
+    }
+
+    /// render this flag with a renderer of either kind. `samples` is only used by raster renderers
+    pub fn render(&self, renderer: &mut AnyRenderer, background: Color, samples: usize) -> Result<(), PrideError> {
+        match renderer {
+            AnyRenderer::Raster(renderer) => render_flag(renderer.as_mut(), self, background, samples),
+            AnyRenderer::Vector(renderer) => render_flag_vector(renderer.as_mut(), self, background),
+        }
+    }
+
+    /// the flag's aspect ratio, which has to be more than 0 for the flag to have any size at all
+    pub fn aspect_ratio(&self) -> Result<f64, PrideError> {
+        match self.aspect.to_number("flag aspect ratio")? {
+            aspect if aspect > 0.0 && aspect.is_finite() => Ok(aspect),
+            _ => Err(PrideError::InvalidNumber { what: "flag aspect ratio", value: self.aspect.0.clone() }),
+        }
+    }
+
+    /// the colors of each of the flag's subsections in order, with gradients represented by their first color
+    pub fn colors(&self) -> Vec<Color> {
+        self.sections.iter()
+            .flat_map(|section| section.subsections.iter())
+            .filter_map(|sub| match &sub.color {
+                FlagFill::Solid(color) => Some(*color),
+                FlagFill::Gradient(gradient) => gradient.stops.first().map(|stop| stop.color),
+            })
+            .collect()
+    }
+
+    /// loads all the images used by the flag, with paths relative to the given directory
+    pub fn load_images(&mut self, directory: &Path) -> Result<(), PrideError> {
+        for shape in self.shapes.iter_mut() {
+            if let FlagShape::Image { path, data, .. } = shape {
+                let path = directory.join(&path);
+                let image = image::open(&path).map_err(|source| PrideError::LoadImage { path, source })?;
+                *data = Some(Arc::new(image.to_rgba8()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// how part of the flag is filled in- either a solid color or a gradient
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum FlagFill {
+    /// solid color, as 3 components or 4 with alpha
+    Solid(Color),
+
+    /// gradient between colors
+    Gradient(FlagGradient),
+}
+
+impl<'de> Deserialize<'de> for FlagFill {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FillVisitor;
+
+        // colors are always arrays and gradients are always maps, so which one it is can be told from the start.
+        // this way errors come from whichever one it is, rather than just saying it's neither
+        impl<'de> Visitor<'de> for FillVisitor {
+            type Value = FlagFill;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a color or a gradient")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Color::deserialize(SeqAccessDeserializer::new(seq)).map(FlagFill::Solid)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                FlagGradient::deserialize(MapAccessDeserializer::new(map)).map(FlagFill::Gradient)
+            }
+        }
 
-    // size of flag in bitmap
+        deserializer.deserialize_any(FillVisitor)
+    }
+}
+
+/// gradient between colors. positions are relative to the bounds of whatever the gradient fills
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FlagGradient {
+    /// shape of the gradient
+    #[serde(default)]
+    pub gradient: FlagGradientKind,
+
+    /// colors along the gradient, in order
+    pub stops: Vec<FlagGradientStop>,
+
+    /// direction of a linear gradient in degrees, going clockwise from left to right
+    #[serde(default)]
+    pub angle: f64,
+
+    /// center of a radial gradient
+    #[serde(default = "default_gradient_center")]
+    pub center: [PartialSize; 2],
+
+    /// distance from the center to where a radial gradient ends
+    #[serde(default = "default_gradient_radius")]
+    pub radius: PartialSize,
+
+    /// color space the stops are blended in
+    #[serde(default)]
+    pub space: ColorSpace,
+}
+
+fn default_gradient_center() -> [PartialSize; 2] { [PartialSize("50%".to_string()), PartialSize("50%".to_string())] }
+fn default_gradient_radius() -> PartialSize { PartialSize("50%".to_string()) }
+
+/// shape of a gradient
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagGradientKind {
+    /// colors change along a straight line
+    #[default]
+    Linear,
+
+    /// colors change going out from a center point
+    Radial,
+}
+
+/// color at a point along a gradient
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FlagGradientStop {
+    /// how far along the gradient the color is- stops without one are spaced out evenly between their neighbours
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<PartialSize>,
+
+    /// color at this point
+    pub color: Color,
+}
+
+impl FlagFill {
+    /// positions this fill inside the given bounds, given as x, y, width and height
+    pub fn to_fill(&self, (x, y, width, height): (f64, f64, f64, f64)) -> Result<Fill, PrideError> {
+        let gradient = match self {
+            Self::Solid(color) => return Ok(Fill::Solid(*color)),
+            Self::Gradient(gradient) => gradient,
+        };
+
+        let kind = match gradient.gradient {
+            FlagGradientKind::Linear => {
+                // the line goes through the middle of the bounds, reaching just far enough that the corners
+                // are at the very start and end
+                let (sin, cos) = gradient.angle.to_radians().sin_cos();
+                let half = ((width * cos).abs() + (height * sin).abs()) / 2.0;
+                let (cx, cy) = (x + width / 2.0, y + height / 2.0);
+
+                GradientKind::Linear { start: (cx - cos * half, cy - sin * half), end: (cx + cos * half, cy + sin * half) }
+            },
+            FlagGradientKind::Radial => {
+                let radius = gradient.radius.to_number("gradient radius")?;
+
+                GradientKind::Radial {
+                    cx: x + gradient.center[0].to_number("gradient center")? * width,
+                    cy: y + gradient.center[1].to_number("gradient center")? * height,
+                    rx: radius * width,
+                    ry: radius * height,
+                }
+            },
+        };
+
+        // fill in missing offsets, keeping them in order
+        let mut offsets: Vec<Option<f64>> = gradient.stops.iter()
+            .map(|stop| stop.offset.as_ref().map(|offset| offset.to_number("gradient stop offset")).transpose())
+            .collect::<Result<_, _>>()?;
+
+        if let Some(first) = offsets.first_mut() {
+            first.get_or_insert(0.0);
+        }
+        if let Some(last) = offsets.last_mut() {
+            last.get_or_insert(1.0);
+        }
+
+        let mut last = 0.0;
+        let mut i = 0;
+        while i < offsets.len() {
+            match offsets[i] {
+                Some(offset) => {
+                    last = f64::max(last, offset);
+                    offsets[i] = Some(last);
+                    i += 1;
+                },
+                None => {
+                    // space out the run of missing offsets between the stops on either side
+                    let end = (i..offsets.len()).find(|j| offsets[*j].is_some()).unwrap_or(offsets.len() - 1);
+                    let next = offsets[end].unwrap_or(last).max(last);
+                    let count = (end - i + 1) as f64;
+
+                    for (n, j) in (i..end).enumerate() {
+                        offsets[j] = Some(last + (next - last) * (n + 1) as f64 / count);
+                    }
+
+                    i = end;
+                },
+            }
+        }
+
+        let stops = offsets.into_iter().zip(gradient.stops.iter())
+            .map(|(offset, stop)| (offset.unwrap_or(0.0), stop.color))
+            .collect();
+
+        Ok(Fill::Gradient(Gradient { kind, stops, space: gradient.space }))
+    }
+}
+
+/// lay out the given flag as shapes, centered in a drawing of the given size
+pub fn layout_flag(flag: &Flag, width: f64, height: f64, background: Color) -> Result<Drawing, PrideError> {
+    let aspect = width / height;
+
+    // size of flag in drawing
     let flag_width;
     let flag_height;
-    let flag_aspect = flag.aspect.as_number().expect("invalid flag aspect ratio");
+    let flag_aspect = flag.aspect_ratio()?;
 
-    // where the flag should be positioned on the bitmap
+    // where the flag should be positioned in the drawing
     let flag_x;
     let flag_y;
 
     // calculate flag size and position
     if aspect > flag_aspect {
-        flag_width = flag_aspect * height as f64;
-        flag_height = height as f64;
-        flag_x = width as f64 / 2.0 - flag_width as f64 / 2.0;
+        flag_width = flag_aspect * height;
+        flag_height = height;
+        flag_x = width / 2.0 - flag_width / 2.0;
         flag_y = 0.0;
     } else if aspect < flag_aspect {
-        flag_width = width as f64;
-        flag_height = 1.0 / flag_aspect * width as f64;
+        flag_width = width;
+        flag_height = 1.0 / flag_aspect * width;
         flag_x = 0.0;
-        flag_y = height as f64 / 2.0 - flag_height as f64 / 2.0;
+        flag_y = height / 2.0 - flag_height / 2.0;
     } else {
-        flag_width = width as f64;
-        flag_height = height as f64;
+        flag_width = width;
+        flag_height = height;
         flag_x = 0.0;
         flag_y = 0.0;
     }
 
-    // create a new bitmap
-    let mut bitmap = Bitmap::new(width, height);
-
-    // fill bitmap with background color
-    bitmap.draw_rect(0, 0, width, height, background);
+    // fill drawing with background color
+    let mut shapes = vec![Shape::Rect { x: 0.0, y: 0.0, width, height, fill: Fill::Solid(background) }];
 
     // x position of current section- used to keep track of where we are and make sure we don't exceed the valid flag width
     let mut section_x = 0.0;
@@ -83,7 +379,7 @@ pub fn render_flag(renderer: &mut Box<dyn Renderer>, flag: &Flag, background: Co
     // iterate over all flag sections (horizontal)
     for section in flag.sections.iter() {
         // raw float value of section width- used to calculate subsection relative width
-        let section_width_raw = section.width.as_number().expect("invalid section width");
+        let section_width_raw = section.width.to_number("section width")?;
 
         // calculate width of section
         let mut section_width = section_width_raw * flag_width;
@@ -99,7 +395,7 @@ pub fn render_flag(renderer: &mut Box<dyn Renderer>, flag: &Flag, background: Co
         // iterate over all flag subsections (vertical)
         for sub in section.subsections.iter() {
             // calculate height of subsection
-            let mut sub_height = sub.height.as_number().expect("invalid subsection height") * flag_height;
+            let mut sub_height = sub.height.to_number("subsection height")? * flag_height;
 
             // clamp section height to edge of flag
             if section_y + sub_height > flag_height {
@@ -107,15 +403,16 @@ pub fn render_flag(renderer: &mut Box<dyn Renderer>, flag: &Flag, background: Co
             }
 
             // calculate width of subsection
-            let mut sub_width = sub.width.as_number().expect("invalid subsection width") * section_width_raw * flag_width;
+            let mut sub_width = sub.width.to_number("subsection width")? * section_width_raw * flag_width;
 
             // clamp section height to edge of flag
             if section_x + sub_width > flag_width {
                 sub_width = flag_width - section_x;
             }
 
-            // render part of flag
-            bitmap.draw_rect((flag_x + section_x).floor() as usize, (flag_y + section_y).floor() as usize, sub_width.ceil() as usize, sub_height.ceil() as usize, sub.color.into());
+            // add part of flag
+            let (x, y) = (flag_x + section_x, flag_y + section_y);
+            shapes.push(Shape::Rect { x, y, width: sub_width, height: sub_height, fill: sub.color.to_fill((x, y, sub_width, sub_height))? });
 
             // increment y position
             section_y += sub_height;
@@ -125,6 +422,482 @@ pub fn render_flag(renderer: &mut Box<dyn Renderer>, flag: &Flag, background: Co
         section_x += section_width;
     }
 
+    // converts a point relative to the flag into a point in the drawing
+    let point = |x: f64, y: f64| (flag_x + x * flag_width, flag_y + y * flag_height);
+
+    // paint shapes over the sections
+    for shape in flag.shapes.iter() {
+        match shape {
+            FlagShape::Triangle { points, color } => {
+                let points: Vec<_> = points.iter()
+                    .map(|[x, y]| Ok(point(x.to_number("triangle point")?, y.to_number("triangle point")?)))
+                    .collect::<Result<_, PrideError>>()?;
+
+                let fill = color.to_fill(polygon_bounds(&points))?;
+                shapes.push(Shape::Polygon { points, fill_rule: FillRule::NonZero, fill });
+            },
+            FlagShape::Chevron { tip, depth, width, color } => {
+                let tip_x = tip[0].to_number("chevron tip")?;
+                let tip_y = tip[1].to_number("chevron tip")?;
+                let depth = depth.to_number("chevron depth")?;
+
+                // outer edge of the chevron
+                let mut points = vec![point(tip_x - depth, 0.0), point(tip_x, tip_y), point(tip_x - depth, 1.0)];
+
+                // inner edge, which is either the outer edge moved left or the left edge of the flag
+                match width {
+                    Some(width) => {
+                        let width = width.to_number("chevron width")?;
+                        points.extend([point(tip_x - depth - width, 1.0), point(tip_x - width, tip_y), point(tip_x - depth - width, 0.0)]);
+                    },
+                    None => {
+                        let left = (tip_x - depth).min(0.0);
+                        points.extend([point(left, 1.0), point(left, 0.0)]);
+                    },
+                }
+
+                let fill = color.to_fill(polygon_bounds(&points))?;
+                shapes.push(Shape::Polygon { points, fill_rule: FillRule::NonZero, fill });
+            },
+            FlagShape::Circle { center, radius, color, stroke_width, stroke_color } => {
+                let (cx, cy) = point(center[0].to_number("circle center")?, center[1].to_number("circle center")?);
+                let radius = radius.to_number("circle radius")? * flag_height;
+
+                shapes.push(Shape::Ellipse { cx, cy, rx: radius, ry: radius, fill: color.to_fill(ellipse_bounds(cx, cy, radius, radius))? });
+
+                if let (Some(width), Some(stroke_color)) = (stroke_width, stroke_color) {
+                    let width = width.to_number("circle stroke width")? * flag_height;
+                    let fill = stroke_color.to_fill(ellipse_bounds(cx, cy, radius + width / 2.0, radius + width / 2.0))?;
+                    shapes.push(Shape::Ring { cx, cy, rx: radius, ry: radius, width, fill });
+                }
+            },
+            FlagShape::Ellipse { center, radius, color, stroke_width, stroke_color } => {
+                let (cx, cy) = point(center[0].to_number("ellipse center")?, center[1].to_number("ellipse center")?);
+                let rx = radius[0].to_number("ellipse radius")? * flag_width;
+                let ry = radius[1].to_number("ellipse radius")? * flag_height;
+
+                shapes.push(Shape::Ellipse { cx, cy, rx, ry, fill: color.to_fill(ellipse_bounds(cx, cy, rx, ry))? });
+
+                if let (Some(width), Some(stroke_color)) = (stroke_width, stroke_color) {
+                    let width = width.to_number("ellipse stroke width")? * flag_height;
+                    let fill = stroke_color.to_fill(ellipse_bounds(cx, cy, rx + width / 2.0, ry + width / 2.0))?;
+                    shapes.push(Shape::Ring { cx, cy, rx, ry, width, fill });
+                }
+            },
+            FlagShape::Ring { center, radius, width, color } => {
+                let (cx, cy) = point(center[0].to_number("ring center")?, center[1].to_number("ring center")?);
+                let radius = radius.to_number("ring radius")? * flag_height;
+                let width = width.to_number("ring width")? * flag_height;
+
+                let fill = color.to_fill(ellipse_bounds(cx, cy, radius + width / 2.0, radius + width / 2.0))?;
+                shapes.push(Shape::Ring { cx, cy, rx: radius, ry: radius, width, fill });
+            },
+            FlagShape::Polygon { points, color, fill_rule } => {
+                let points: Vec<_> = points.iter()
+                    .map(|[x, y]| Ok(point(x.to_number("polygon point")?, y.to_number("polygon point")?)))
+                    .collect::<Result<_, PrideError>>()?;
+
+                let fill = color.to_fill(polygon_bounds(&points))?;
+                shapes.push(Shape::Polygon { points, fill_rule: *fill_rule, fill });
+            },
+            FlagShape::Star { center, radius, points: tips, inner_ratio, rotation, color } => {
+                let (cx, cy) = point(center[0].to_number("star center")?, center[1].to_number("star center")?);
+                let radius = radius.to_number("star radius")? * flag_height;
+
+                // alternate between tips and inner corners, going clockwise from the top
+                let points: Vec<_> = (0..tips * 2).map(|i| {
+                    let angle = (rotation + i as f64 * 180.0 / *tips as f64).to_radians();
+                    let distance = if i % 2 == 0 { radius } else { radius * inner_ratio };
+                    (cx + angle.sin() * distance, cy - angle.cos() * distance)
+                }).collect();
+
+                let fill = color.to_fill(polygon_bounds(&points))?;
+                shapes.push(Shape::Polygon { points, fill_rule: FillRule::NonZero, fill });
+            },
+            FlagShape::Image { center, width, height, filter, data, .. } => {
+                // images that haven't been loaded are left out
+                let image = match data {
+                    Some(image) => image,
+                    None => continue,
+                };
+
+                let (cx, cy) = point(center[0].to_number("image center")?, center[1].to_number("image center")?);
+                let width = width.to_number("image width")? * flag_width;
+                let height = match height {
+                    Some(height) => height.to_number("image height")? * flag_height,
+                    None => width * image.height() as f64 / image.width().max(1) as f64,
+                };
+
+                shapes.push(Shape::Image { x: cx - width / 2.0, y: cy - height / 2.0, width, height, image: image.clone(), filter: *filter });
+            },
+        }
+    }
+
+    // the name shown to people is preferred, since it's used for things like captions
+    let name = flag.display_name.clone().or_else(|| flag.name.clone());
+
+    Ok(Drawing { width, height, shapes, name })
+}
+
+/// gets the bounds of a polygon as x, y, width and height
+fn polygon_bounds(points: &[(f64, f64)]) -> (f64, f64, f64, f64) {
+    let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+/// gets the bounds of an ellipse as x, y, width and height
+fn ellipse_bounds(cx: f64, cy: f64, rx: f64, ry: f64) -> (f64, f64, f64, f64) {
+    (cx - rx, cy - ry, rx * 2.0, ry * 2.0)
+}
+
+/// most samples that can be taken per pixel in each direction, so up to 256 samples make up each pixel
+pub const MAX_SAMPLES: usize = 16;
+
+/// rasterize the given flag to a bitmap of the given size. `samples` is how many samples to take per pixel
+/// in each direction, up to `MAX_SAMPLES`- anything over 1 smooths out edges by averaging them
+pub fn rasterize_flag(flag: &Flag, width: usize, height: usize, background: Color, samples: usize) -> Result<Bitmap, PrideError> {
+    let samples = samples.clamp(1, MAX_SAMPLES);
+
+    // lay out the flag at a higher resolution if we're supersampling, so it can be scaled down afterwards
+    let drawing = layout_flag(flag, (width * samples) as f64, (height * samples) as f64, background)?;
+
+    // images are only scaled once, to the size they'll end up at once the flag is scaled down. any finer and they'd
+    // take up as much memory as drawing the whole flag at the higher resolution would
+    let images = drawing.shapes.iter()
+        .map(|shape| match shape {
+            Shape::Image { x, y, width, height, image, filter } => {
+                let (x0, y0, x1, y1) = image_bounds(*x, *y, *width, *height);
+                let (width, height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+
+                if width > 0 && height > 0 {
+                    let (width, height) = (width.div_ceil(samples) as u32, height.div_ceil(samples) as u32);
+                    Some(imageops::resize(&**image, width, height, (*filter).into()))
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut bitmap = Bitmap::new(width, height);
+
+    // each row of pixels is drawn and scaled down on its own, so the flag is never all kept at the higher resolution.
+    // at 1920x1080 with 16 samples each way that would take up gigabytes
+    let mut band = Bitmap::new(width * samples, samples);
+
+    for (row, data) in bitmap.data.iter_mut().enumerate() {
+        let top = row * samples;
+        band.data.iter_mut().for_each(|row| row.fill(Color::TRANSPARENT));
+
+        // draw all the shapes in order, moved up so that this row ends up at the top of the band
+        for (shape, scaled) in drawing.shapes.iter().zip(images.iter()) {
+            match shape {
+                Shape::Rect { x, y, width, height, fill } => {
+                    // edges are rounded to the closest pixel boundary, so neighbouring rectangles never overlap or leave gaps
+                    let (x0, y0) = (x.round().max(0.0) as usize, y.round().max(0.0) as usize);
+                    let (x1, y1) = ((x + width).round().max(0.0) as usize, (y + height).round().max(0.0) as usize);
+                    let (y0, y1) = (y0.clamp(top, top + samples), y1.clamp(top, top + samples));
+
+                    band.draw_rect(x0, y0 - top, x1.saturating_sub(x0), y1.saturating_sub(y0), &Shifted(fill, top));
+                },
+                Shape::Polygon { points, fill_rule, fill } => {
+                    let points = points.iter().map(|(x, y)| (*x, y - top as f64)).collect::<Vec<_>>();
+                    band.fill_polygon(&points, *fill_rule, &Shifted(fill, top));
+                },
+                Shape::Ellipse { cx, cy, rx, ry, fill } => band.fill_ellipse(*cx, cy - top as f64, *rx, *ry, &Shifted(fill, top)),
+                Shape::Ring { cx, cy, rx, ry, width, fill } => band.fill_ring(*cx, cy - top as f64, *rx, *ry, *width, &Shifted(fill, top)),
+                Shape::Image { x, y, width, height, .. } => {
+                    if let Some(scaled) = scaled {
+                        let (x0, y0, x1, y1) = image_bounds(*x, *y, *width, *height);
+                        band.draw_image(x0, y0 - top as isize, (x1 - x0) as usize, (y1 - y0) as usize, scaled);
+                    }
+                },
+            }
+        }
+
+        if samples > 1 {
+            *data = band.downsample(samples).data.swap_remove(0);
+        } else {
+            data.copy_from_slice(&band.data[0]);
+        }
+    }
+
+    Ok(bitmap)
+}
+
+/// edges of an image in whole pixels, rounded the same way as rectangles
+fn image_bounds(x: f64, y: f64, width: f64, height: f64) -> (isize, isize, isize, isize) {
+    let (x0, y0) = (x.round() as isize, y.round() as isize);
+    let (x1, y1) = ((x + width).round() as isize, (y + height).round() as isize);
+
+    (x0, y0, x1.max(x0), y1.max(y0))
+}
+
+/// paint that's been moved up by some number of pixels, so it stays lined up with shapes drawn into part of a bitmap
+struct Shifted<'a, P: Paint>(&'a P, usize);
+
+impl<P: Paint> Paint for Shifted<'_, P> {
+    fn color_at(&self, x: f64, y: f64) -> Color {
+        self.0.color_at(x, y + self.1 as f64)
+    }
+}
+
+/// shape painted over the flag. positions are relative to the width and height of the flag
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FlagShape {
+    /// triangle between three points
+    Triangle {
+        /// corners of triangle
+        points: [[PartialSize; 2]; 3],
+
+        /// color of triangle
+        color: FlagFill,
+    },
+
+    /// arrow shape pointing right, with its arms reaching the top and bottom edges of the flag
+    Chevron {
+        /// position of the tip of the chevron
+        tip: [PartialSize; 2],
+
+        /// horizontal distance from the tip back to the ends of the arms
+        depth: PartialSize,
+
+        /// horizontal thickness of the chevron- if not set, it's filled in all the way to the left edge of the flag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        width: Option<PartialSize>,
+
+        /// color of chevron
+        color: FlagFill,
+    },
+
+    /// circle, with its radius relative to the height of the flag so that it stays round
+    Circle {
+        /// center of circle
+        center: [PartialSize; 2],
+
+        /// radius of circle
+        radius: PartialSize,
+
+        /// fill color of circle
+        color: FlagFill,
+
+        /// width of the outline around the circle, relative to the height of the flag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stroke_width: Option<PartialSize>,
+
+        /// color of the outline around the circle
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stroke_color: Option<FlagFill>,
+    },
+
+    /// ellipse, with its horizontal and vertical radii relative to the width and height of the flag
+    Ellipse {
+        /// center of ellipse
+        center: [PartialSize; 2],
+
+        /// horizontal and vertical radius of ellipse
+        radius: [PartialSize; 2],
+
+        /// fill color of ellipse
+        color: FlagFill,
+
+        /// width of the outline around the ellipse, relative to the height of the flag
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stroke_width: Option<PartialSize>,
+
+        /// color of the outline around the ellipse
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stroke_color: Option<FlagFill>,
+    },
+
+    /// unfilled circle, with its radius and width relative to the height of the flag
+    Ring {
+        /// center of ring
+        center: [PartialSize; 2],
+
+        /// radius of ring, measured to the middle of its band
+        radius: PartialSize,
+
+        /// thickness of ring
+        width: PartialSize,
+
+        /// color of ring
+        color: FlagFill,
+    },
+
+    /// polygon made of any number of points
+    Polygon {
+        /// corners of polygon, in order
+        points: Vec<[PartialSize; 2]>,
+
+        /// color of polygon
+        color: FlagFill,
+
+        /// how to fill the polygon where its edges cross each other
+        #[serde(default)]
+        fill_rule: FillRule,
+    },
+
+    /// star, with its radius relative to the height of the flag so that it keeps its shape
+    Star {
+        /// center of star
+        center: [PartialSize; 2],
+
+        /// distance from the center to the tips of the star
+        radius: PartialSize,
+
+        /// number of tips
+        #[serde(default = "default_star_points")]
+        points: usize,
+
+        /// distance from the center to the inner corners of the star, relative to its radius
+        #[serde(default = "default_star_inner_ratio")]
+        inner_ratio: f64,
+
+        /// clockwise rotation in degrees- the first tip points straight up if not rotated
+        #[serde(default)]
+        rotation: f64,
+
+        /// color of star
+        color: FlagFill,
+    },
+
+    /// PNG or JPEG image, stretched to the given size
+    Image {
+        /// where the image is, relative to the flag file
+        path: PathBuf,
+
+        /// center of image
+        center: [PartialSize; 2],
+
+        /// width of image
+        width: PartialSize,
+
+        /// height of image- if not set, it's picked to keep the image's aspect ratio
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        height: Option<PartialSize>,
+
+        /// filter used to scale the image
+        #[serde(default)]
+        filter: ImageFilter,
+
+        /// the image itself, once it's been loaded
+        #[serde(skip)]
+        data: Option<Arc<RgbaImage>>,
+    },
+}
+
+fn default_star_points() -> usize { 5 }
+
+/// gives a regular five pointed star, where the edges of opposite tips line up
+fn default_star_inner_ratio() -> f64 { 0.381966 }
+
+/// render the given flag with the given renderer
+pub fn render_flag(renderer: &mut dyn Renderer, flag: &Flag, background: Color, samples: usize) -> Result<(), PrideError> {
+    // get size we can render to
+    let (width, height) = renderer.get_size()?;
+
+    let bitmap = rasterize_flag(flag, width, height, background, samples)?;
+
     // render bitmap to screen
-    renderer.render(&bitmap);
+    renderer.render(&bitmap)
+}
+
+/// render the given flag with the given vector renderer, skipping rasterization entirely
+pub fn render_flag_vector(renderer: &mut dyn VectorRenderer, flag: &Flag, background: Color) -> Result<(), PrideError> {
+    // get size we can render to
+    let aspect = flag.aspect_ratio()?;
+    let (width, height) = renderer.get_size(aspect);
+
+    let drawing = layout_flag(flag, width, height, background)?;
+
+    // write drawing out
+    renderer.render(&drawing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// gets the offsets of the stops of a gradient once it's been positioned
+    fn offsets(yaml: &str) -> Vec<f64> {
+        let fill: FlagFill = serde_yaml::from_str(yaml).unwrap();
+
+        match fill.to_fill((0.0, 0.0, 100.0, 100.0)).unwrap() {
+            Fill::Gradient(gradient) => gradient.stops.iter().map(|(offset, _)| *offset).collect(),
+            Fill::Solid(_) => panic!("expected a gradient"),
+        }
+    }
+
+    #[test]
+    fn aspect_ratios_have_to_be_positive() {
+        for aspect in ["0", "0%", "1/0", "inf"] {
+            let flag = Flag::from_yaml(&format!("{{ aspect: \"{}\", sections: [] }}", aspect)).unwrap();
+            assert!(matches!(flag.aspect_ratio(), Err(PrideError::InvalidNumber { .. })), "{}", aspect);
+        }
+
+        assert_eq!(Flag::from_yaml("{ aspect: 5/3, sections: [] }").unwrap().aspect_ratio().unwrap(), 5.0 / 3.0);
+    }
+
+    #[test]
+    fn supersampling_a_row_at_a_time_matches_scaling_down_the_whole_flag() {
+        let gradient = r#"{
+            aspect: 3/2,
+            sections: [ { width: 100%, subsections: [ { width: 100%, height: 100%, color: { stops: [ { color: [ 255, 0, 0 ] }, { color: [ 0, 0, 255, 128 ] } ], angle: 30 } } ] } ],
+            shapes: [ { type: star, center: [ 40%, 55% ], radius: 33.3%, color: { gradient: radial, stops: [ { color: [ 0, 0, 0 ] }, { color: [ 255, 255, 255 ] } ] } } ],
+        }"#;
+
+        let flags = crate::library::BUILTIN_FLAGS.iter().map(|(_, yaml)| *yaml).chain([gradient]);
+
+        for yaml in flags {
+            let flag = Flag::from_yaml(yaml).unwrap();
+            let background = Color::new(0, 0, 0);
+
+            let banded = rasterize_flag(&flag, 37, 23, background, 4).unwrap();
+            let whole = rasterize_flag(&flag, 37 * 4, 23 * 4, background, 1).unwrap().downsample(4);
+
+            assert!(banded.data == whole.data, "{}", yaml);
+        }
+    }
+
+    #[test]
+    fn fills_are_told_apart_by_their_shape() {
+        assert!(matches!(serde_yaml::from_str("[ 255, 0, 0 ]"), Ok(FlagFill::Solid(_))));
+        assert!(matches!(serde_json::from_str("{ \"stops\": [] }"), Ok(FlagFill::Gradient(_))));
+    }
+
+    #[test]
+    fn bad_fills_say_what_was_wrong_with_them() {
+        for (yaml, message) in [
+            ("[ 300, 0, 0 ]", "expected u8"),
+            ("[ 255, 0 ]", "invalid length 2"),
+            ("{ stops: [ { color: [ 0, 0 ] } ] }", "invalid length 2"),
+            ("{ angle: 90 }", "missing field `stops`"),
+            ("red", "expected a color or a gradient"),
+        ] {
+            let err = serde_yaml::from_str::<FlagFill>(yaml).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", yaml, err);
+        }
+    }
+
+    #[test]
+    fn missing_offsets_are_spaced_out_evenly() {
+        let stops = offsets("stops: [ { color: [ 0, 0, 0 ] }, { color: [ 0, 0, 0 ] }, { color: [ 0, 0, 0 ] } ]");
+        assert_eq!(stops, [0.0, 0.5, 1.0]);
+
+        let stops = offsets("stops: [ { color: [ 0, 0, 0 ] }, { color: [ 0, 0, 0 ] }, { offset: 80%, color: [ 0, 0, 0 ] }, { color: [ 0, 0, 0 ] } ]");
+        assert_eq!(stops, [0.0, 0.4, 0.8, 1.0]);
+    }
+
+    #[test]
+    fn offsets_never_go_backwards() {
+        let stops = offsets("stops: [ { offset: 50%, color: [ 0, 0, 0 ] }, { offset: 20%, color: [ 0, 0, 0 ] }, { color: [ 0, 0, 0 ] } ]");
+        assert_eq!(stops, [0.5, 0.5, 1.0]);
+    }
 }
@@ -0,0 +1,145 @@
+//! formats flags can be written in, and telling them apart
+
+use enum_iterator::Sequence;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{fmt, path::Path, str::FromStr};
+
+/// format of a flag file
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Sequence)]
+pub enum FlagFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl FlagFormat {
+    /// name of the format as it's given on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+            Self::Toml => "toml",
+        }
+    }
+
+    /// extensions of files in this format, most common first
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Yaml => &["yml", "yaml"],
+            Self::Json => &["json"],
+            Self::Toml => &["toml"],
+        }
+    }
+
+    /// picks a format based on the extension of a file, if it has one that's known
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        enum_iterator::all::<Self>().find(|format| format.extensions().contains(&extension.as_str()))
+    }
+
+    /// guesses the format of a flag from its contents. anything that isn't obviously JSON or TOML is taken to be YAML,
+    /// since that's what flags are usually written in
+    pub fn detect(text: &str) -> Self {
+        lazy_static! {
+            // TOML has tables or keys set with "=" at the start of lines, where YAML would have keys set with ":" or
+            // indented text
+            static ref TOML: Regex = Regex::new(r#"(?m)^(\[|[\w."'-]+[ \t]*=)"#).unwrap();
+        }
+
+        // YAML can look a lot like either of the others, so they're only picked if the whole flag can be parsed as them
+        if text.trim_start().starts_with('{') && serde_json::from_str::<serde_json::Value>(text).is_ok() {
+            Self::Json
+        } else if TOML.is_match(text) && text.parse::<toml::Table>().is_ok() {
+            Self::Toml
+        } else {
+            Self::Yaml
+        }
+    }
+}
+
+impl FromStr for FlagFormat {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.to_lowercase();
+
+        enum_iterator::all::<Self>()
+            .find(|format| format.name() == input || format.extensions().contains(&input.as_str()))
+            .ok_or_else(|| "format should be \"yaml\", \"json\" or \"toml\"".to_string())
+    }
+}
+
+impl fmt::Display for FlagFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name().to_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flag::Flag;
+
+    #[test]
+    fn detects_json() {
+        assert_eq!(FlagFormat::detect(r#"{ "aspect": 2, "sections": [] }"#), FlagFormat::Json);
+        assert_eq!(FlagFormat::detect("\n  {\n    \"aspect\": \"5/3\",\n    \"sections\": []\n  }\n"), FlagFormat::Json);
+    }
+
+    #[test]
+    fn detects_toml() {
+        assert_eq!(FlagFormat::detect("aspect = 2\nsections = []\n"), FlagFormat::Toml);
+        assert_eq!(FlagFormat::detect("# comment\naspect = \"5/3\"\n\n[[sections]]\nwidth = \"100%\"\n"), FlagFormat::Toml);
+        assert_eq!(FlagFormat::detect("[[sections]]\nwidth = 1\nsubsections = []\n"), FlagFormat::Toml);
+    }
+
+    #[test]
+    fn detects_yaml() {
+        assert_eq!(FlagFormat::detect("aspect: 2\nsections: []\n"), FlagFormat::Yaml);
+        assert_eq!(FlagFormat::detect("{ aspect: 2, sections: [] }"), FlagFormat::Yaml);
+        assert_eq!(FlagFormat::detect(r#"{ "aspect": 2, sections: [] }"#), FlagFormat::Yaml);
+        assert_eq!(FlagFormat::detect(""), FlagFormat::Yaml);
+    }
+
+    #[test]
+    fn yaml_with_lines_starting_with_brackets_stays_yaml() {
+        let yaml = "aspect: 2\nsections: [ { width: 100%, subsections:\n[ { width: 100%, height: 100%, color: [ 0, 0, 0 ] } ] } ]\n";
+
+        assert_eq!(FlagFormat::detect(yaml), FlagFormat::Yaml);
+        assert!(Flag::parse(yaml).is_ok());
+    }
+
+    #[test]
+    fn yaml_with_equals_signs_stays_yaml() {
+        for yaml in [
+            "aspect: 2\nsections: []\ndescription: a = b\n",
+            "aspect: 2\nsections: []\ndescription: \"one\nx = y\"\n",
+            "aspect=2: yes\naspect: 2\nsections: []\n",
+        ] {
+            assert_eq!(FlagFormat::detect(yaml), FlagFormat::Yaml, "{:?}", yaml);
+            assert!(Flag::parse(yaml).is_ok(), "{:?}", yaml);
+        }
+    }
+
+    #[test]
+    fn formats_come_from_extensions() {
+        assert_eq!(FlagFormat::from_path(Path::new("flags/trans.yml")), Some(FlagFormat::Yaml));
+        assert_eq!(FlagFormat::from_path(Path::new("trans.yaml")), Some(FlagFormat::Yaml));
+        assert_eq!(FlagFormat::from_path(Path::new("TRANS.YAML")), Some(FlagFormat::Yaml));
+        assert_eq!(FlagFormat::from_path(Path::new("trans.Json")), Some(FlagFormat::Json));
+        assert_eq!(FlagFormat::from_path(Path::new("trans.toml")), Some(FlagFormat::Toml));
+        assert_eq!(FlagFormat::from_path(Path::new("trans.txt")), None);
+        assert_eq!(FlagFormat::from_path(Path::new("trans")), None);
+        assert_eq!(FlagFormat::from_path(Path::new(".yaml")), None);
+    }
+
+    #[test]
+    fn formats_are_parsed_by_name_or_extension() {
+        assert_eq!("json".parse(), Ok(FlagFormat::Json));
+        assert_eq!("YML".parse(), Ok(FlagFormat::Yaml));
+        assert_eq!("Toml".parse(), Ok(FlagFormat::Toml));
+        assert!("xml".parse::<FlagFormat>().is_err());
+    }
+}
@@ -0,0 +1,21 @@
+//! draws pride flags (and other flags, if you want) to a terminal, an image or a vector file
+//!
+//! flags are loaded by name or path with `load_flag`, or with `Flag::load` or `Flag::parse`, then either rasterized to a `Bitmap` with
+//! `Flag::rasterize` or drawn with a renderer made by `create_renderer` using `Flag::render`
+
+pub mod bitmap;
+pub mod error;
+pub mod flag;
+pub mod format;
+pub mod library;
+pub mod render;
+pub mod shape;
+pub mod util;
+
+pub use crate::bitmap::{Bitmap, Color};
+pub use crate::error::PrideError;
+pub use crate::flag::{Flag, layout_flag, rasterize_flag, render_flag, render_flag_vector};
+pub use crate::format::FlagFormat;
+pub use crate::library::{find_flag, load_flag, read_flag};
+pub use crate::render::{AnyRenderer, CreatedRenderer, Renderer, Renderers, VectorRenderer, create_renderer};
+pub use crate::shape::Drawing;
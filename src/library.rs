@@ -0,0 +1,315 @@
+//! flags built into pride, and finding flags by name
+
+use crate::error::PrideError;
+use crate::flag::Flag;
+use crate::format::FlagFormat;
+use std::{
+    env,
+    fs,
+    iter,
+    path::{Path, PathBuf},
+};
+
+/// flags compiled into pride, as their names and YAML. this has to match what's in the flags directory, which is
+/// checked by the tests
+pub const BUILTIN_FLAGS: &[(&str, &str)] = &[
+    ("agender", include_str!("../flags/agender.yml")),
+    ("aroace", include_str!("../flags/aroace.yml")),
+    ("aromantic", include_str!("../flags/aromantic.yml")),
+    ("asexual", include_str!("../flags/asexual.yml")),
+    ("bear", include_str!("../flags/bear.yml")),
+    ("bisexual", include_str!("../flags/bisexual.yml")),
+    ("demiboy", include_str!("../flags/demiboy.yml")),
+    ("demigirl", include_str!("../flags/demigirl.yml")),
+    ("demisexual", include_str!("../flags/demisexual.yml")),
+    ("gay-men", include_str!("../flags/gay-men.yml")),
+    ("genderfluid", include_str!("../flags/genderfluid.yml")),
+    ("genderqueer", include_str!("../flags/genderqueer.yml")),
+    ("gilbert-baker", include_str!("../flags/gilbert-baker.yml")),
+    ("intersex", include_str!("../flags/intersex.yml")),
+    ("lesbian", include_str!("../flags/lesbian.yml")),
+    ("nonbinary", include_str!("../flags/nonbinary.yml")),
+    ("pansexual", include_str!("../flags/pansexual.yml")),
+    ("philly", include_str!("../flags/philly.yml")),
+    ("polysexual", include_str!("../flags/polysexual.yml")),
+    ("progress", include_str!("../flags/progress.yml")),
+    ("progress-intersex", include_str!("../flags/progress-intersex.yml")),
+    ("rainbow", include_str!("../flags/rainbow.yml")),
+    ("trans", include_str!("../flags/trans.yml")),
+];
+
+/// how similar two names have to be, from 0 to 1, for one to be taken as a misspelling of the other
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// most flags suggested when one can't be found
+const MAX_SUGGESTIONS: usize = 3;
+
+/// directories flags are looked for in by name, most important first. this is `$XDG_DATA_HOME/pride/flags`
+/// (or `~/.local/share/pride/flags`), then `pride/flags` in each of `$XDG_DATA_DIRS`
+pub fn flag_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => directories.push(PathBuf::from(dir)),
+        None => if let Some(home) = env::var_os("HOME").filter(|dir| !dir.is_empty()) {
+            directories.push(Path::new(&home).join(".local").join("share"));
+        },
+    }
+
+    let data_dirs = env::var_os("XDG_DATA_DIRS").filter(|dirs| !dirs.is_empty()).unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    directories.extend(env::split_paths(&data_dirs));
+
+    // relative paths aren't allowed, so they're ignored
+    directories.into_iter().filter(|dir| dir.is_absolute()).map(|dir| dir.join("pride").join("flags")).collect()
+}
+
+/// finds a flag by name or by one of its aliases, looking in the flag directories before the built-in flags so they
+/// can be replaced
+pub fn find_flag(name: &str) -> Result<Flag, PrideError> {
+    find_flag_with(name, Flag::load)
+}
+
+/// finds a flag like `find_flag`, reading flag files with the given function
+fn find_flag_with(name: &str, read: fn(&Path) -> Result<Flag, PrideError>) -> Result<Flag, PrideError> {
+    if let Some(flag) = find_by_name(name, read) {
+        return flag;
+    }
+
+    let flags = all_flags();
+
+    // the flag is found again by the name it was found by, since the flags we have were read without their images
+    if let Some((found, _)) = flags.iter().find(|(_, flag)| flag.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))) {
+        if let Some(flag) = find_by_name(found, read) {
+            return flag;
+        }
+    }
+
+    Err(PrideError::UnknownFlag { name: name.to_string(), suggestions: suggestions(name, &flags) })
+}
+
+/// finds a flag by the name of its file or built-in flag, without looking at aliases, reading flag files with the
+/// given function. flags that don't have a name are named after the file or built-in flag they were found as
+fn find_by_name(name: &str, read: fn(&Path) -> Result<Flag, PrideError>) -> Option<Result<Flag, PrideError>> {
+    for directory in flag_directories() {
+        for extension in enum_iterator::all::<FlagFormat>().flat_map(FlagFormat::extensions) {
+            let path = directory.join(format!("{}.{}", name, extension));
+
+            if path.is_file() {
+                return Some(read(&path).map(|flag| with_name(flag, name)));
+            }
+        }
+    }
+
+    let (builtin, yaml) = BUILTIN_FLAGS.iter().find(|(builtin, _)| builtin.eq_ignore_ascii_case(name))?;
+
+    Some(Flag::from_yaml(yaml).map(|flag| with_name(flag, builtin)))
+}
+
+/// names a flag if it doesn't already have a name
+fn with_name(mut flag: Flag, name: &str) -> Flag {
+    flag.name.get_or_insert_with(|| name.to_string());
+    flag
+}
+
+/// loads a flag from a file if given a path to one, or finds it by name otherwise
+pub fn load_flag(name_or_path: &str) -> Result<Flag, PrideError> {
+    load_flag_with(name_or_path, Flag::load)
+}
+
+/// finds a flag like `load_flag`, but without loading its images, for when only what the flag describes is needed
+pub fn read_flag(name_or_path: &str) -> Result<Flag, PrideError> {
+    load_flag_with(name_or_path, Flag::read)
+}
+
+/// finds a flag like `load_flag`, reading flag files with the given function
+fn load_flag_with(name_or_path: &str, read: fn(&Path) -> Result<Flag, PrideError>) -> Result<Flag, PrideError> {
+    let path = Path::new(name_or_path);
+
+    // anything that looks like a path is treated as one, even if it doesn't exist, so the error makes sense
+    if path.is_file() || path.extension().is_some() || path.components().count() > 1 {
+        read(path)
+    } else {
+        find_flag_with(name_or_path, read)
+    }
+}
+
+/// every flag that can be found by name along with the name it's found by, sorted by name. flags are read without
+/// their images, and ones that can't be read are left out
+pub fn all_flags() -> Vec<(String, Flag)> {
+    flag_names().into_iter()
+        .filter_map(|name| match find_by_name(&name, Flag::read) {
+            Some(Ok(flag)) => Some((name, flag)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// every flag with a name, alias or tag that contains or looks like the given term, or a display name that contains
+/// it, along with the name it's found by
+pub fn search_flags(term: &str) -> Vec<(String, Flag)> {
+    let term = term.to_lowercase();
+
+    all_flags().into_iter()
+        .filter(|(name, flag)| {
+            let in_display_name = flag.display_name.as_ref().is_some_and(|display_name| display_name.to_lowercase().contains(&term));
+
+            in_display_name || iter::once(name).chain(&flag.aliases).chain(&flag.tags)
+                .any(|field| similarity(&term, &field.to_lowercase()) >= SIMILARITY_THRESHOLD)
+        })
+        .collect()
+}
+
+/// names of flags with names or aliases that look like the given name, most similar first
+pub fn suggest_flags(name: &str) -> Vec<String> {
+    suggestions(name, &all_flags())
+}
+
+/// picks the flags out of the given ones with names or aliases that look like the given name, most similar first
+fn suggestions(name: &str, flags: &[(String, Flag)]) -> Vec<String> {
+    let name = name.to_lowercase();
+
+    let mut similar: Vec<(f64, &String)> = flags.iter()
+        .map(|(flag_name, flag)| {
+            let score = iter::once(flag_name).chain(&flag.aliases)
+                .map(|other| similarity(&name, &other.to_lowercase()))
+                .fold(0.0, f64::max);
+
+            (score, flag_name)
+        })
+        .filter(|(score, _)| *score >= SIMILARITY_THRESHOLD)
+        .collect();
+
+    similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+    similar.into_iter().take(MAX_SUGGESTIONS).map(|(_, name)| name.clone()).collect()
+}
+
+/// how alike two names are, from 0 to 1. names containing the other one (as long as it isn't too short to mean
+/// anything) count as very similar, so partial names still find what they're part of
+fn similarity(term: &str, name: &str) -> f64 {
+    if term.len() >= 3 && name.contains(term) {
+        0.95_f64.max(strsim::jaro_winkler(term, name))
+    } else {
+        strsim::jaro_winkler(term, name)
+    }
+}
+
+/// names of every flag that can be found by name, sorted and without duplicates
+pub fn flag_names() -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_FLAGS.iter().map(|(name, _)| name.to_string()).collect();
+
+    for directory in flag_directories() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let is_flag = FlagFormat::from_path(&path).is_some();
+
+            if let (true, Some(name)) = (is_flag, path.file_stem()) {
+                names.push(name.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitmap::Color;
+    use crate::flag::rasterize_flag;
+
+    /// makes an empty flag with the given aliases, along with the name it's found by
+    fn flag(name: &str, aliases: &[&str]) -> (String, Flag) {
+        let mut flag = Flag::from_yaml("{ aspect: 1, sections: [] }").unwrap();
+        flag.aliases = aliases.iter().map(|alias| alias.to_string()).collect();
+
+        (name.to_string(), flag)
+    }
+
+    #[test]
+    fn every_flag_file_is_built_in() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("flags");
+
+        let mut files = fs::read_dir(directory).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .map(|path| (path.file_stem().unwrap().to_str().unwrap().to_string(), fs::read_to_string(&path).unwrap()))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        let builtin = BUILTIN_FLAGS.iter().map(|(name, yaml)| (name.to_string(), yaml.to_string())).collect::<Vec<_>>();
+
+        assert_eq!(builtin.iter().map(|(name, _)| name).collect::<Vec<_>>(), files.iter().map(|(name, _)| name).collect::<Vec<_>>());
+        assert!(builtin == files, "built in flags don't match their files");
+    }
+
+    #[test]
+    fn every_built_in_flag_can_be_drawn() {
+        for (name, yaml) in BUILTIN_FLAGS {
+            let flag = Flag::from_yaml(yaml).unwrap_or_else(|err| panic!("{}: {}", name, err));
+            rasterize_flag(&flag, 50, 30, Color::new(0, 0, 0), 2).unwrap_or_else(|err| panic!("{}: {}", name, err));
+        }
+    }
+
+    #[test]
+    fn flags_found_by_name_are_named_after_it() {
+        let flag = find_by_name("Trans", Flag::read).unwrap().unwrap();
+        assert_eq!(flag.name.as_deref(), Some("trans"));
+    }
+
+    #[test]
+    fn flags_read_from_files_are_left_as_written() {
+        let path = env::temp_dir().join(format!("pride-unnamed-{}.yml", std::process::id()));
+        fs::write(&path, "aspect: 2\nsections: []\n").unwrap();
+
+        let flag = read_flag(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(flag.unwrap().name, None);
+    }
+
+    #[test]
+    fn names_containing_the_term_are_very_similar() {
+        assert_eq!(similarity("trans", "trans"), 1.0);
+        assert!(similarity("trans", "transgender") >= 0.95);
+        assert!(similarity("gender", "genderqueer") >= 0.95);
+    }
+
+    #[test]
+    fn short_terms_only_match_by_spelling() {
+        assert_eq!(similarity("bi", "bisexual"), strsim::jaro_winkler("bi", "bisexual"));
+        assert!(similarity("xy", "xylophone-flag") < 0.95);
+    }
+
+    #[test]
+    fn suggestions_are_most_similar_first() {
+        let flags = [flag("lesbian-flag", &[]), flag("trans", &[]), flag("lesbian", &[])];
+
+        assert_eq!(suggestions("lesbain", &flags), ["lesbian", "lesbian-flag"]);
+    }
+
+    #[test]
+    fn suggestions_look_at_aliases() {
+        let flags = [flag("trans", &["transgender"]), flag("rainbow", &[])];
+
+        assert_eq!(suggestions("TRANSGNDER", &flags), ["trans"]);
+    }
+
+    #[test]
+    fn suggestions_leave_out_unrelated_flags() {
+        let flags = [flag("trans", &[]), flag("rainbow", &[])];
+
+        assert!(suggestions("xyzzy", &flags).is_empty());
+    }
+
+    #[test]
+    fn suggestions_are_limited() {
+        let flags = [flag("demiboy", &[]), flag("demigirl", &[]), flag("demisexual", &[]), flag("demiromantic", &[])];
+
+        assert_eq!(suggestions("demi", &flags).len(), MAX_SUGGESTIONS);
+    }
+}
@@ -1,20 +1,22 @@
-pub mod render;
-pub mod bitmap;
-pub mod util;
-pub mod flag;
-
-use crate::bitmap::Color;
-use crate::flag::{Flag, render_flag};
-use crate::render::{
-    create_renderer,
-    list_renderers,
-    list_options,
-    Renderers,
+use clap::{Parser, Subcommand};
+use pride::{
+    Color,
+    Flag,
+    FlagFormat,
+    flag::MAX_SAMPLES,
+    PrideError,
+    library::{flag_names, load_flag, read_flag, search_flags, suggest_flags},
+    render::{
+        create_renderer,
+        list_renderers,
+        list_options,
+        Renderers,
+    },
+    util::quoted_list,
 };
-use clap::Parser;
 use std::{
-    fs,
-    path::PathBuf,
+    io::{self, ErrorKind, IsTerminal, stdin, stdout},
+    path::Path,
     process::exit,
     str::FromStr,
 };
@@ -22,9 +24,21 @@ use std::{
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    /// path to the flag to view
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// name of the flag to view (try "--list-flags" to list all available flags), a path to a YAML, JSON or TOML flag
+    /// file, or "-" to read the flag from stdin
     #[clap(short, long)]
-    flag: PathBuf,
+    flag: Option<String>,
+
+    /// the flag to view, written out as YAML
+    #[clap(long, conflicts_with = "flag")]
+    flag_yaml: Option<String>,
+
+    /// list all available flags
+    #[clap(long)]
+    list_flags: bool,
 
     /// which renderer to use (try "--renderer list" to list all available renderers)
     #[clap(short = 'r', long)]
@@ -34,16 +48,201 @@ struct Args {
     #[clap(short = 'o', long)]
     renderer_options: Option<String>,
 
-    /// background color in hex notation
+    /// background color in hex notation, with optional alpha (#rrggbbaa), or "transparent"
     #[clap(short, long, default_value_t = Color::new(0, 0, 0))]
     background: Color,
+
+    /// anti-aliasing level from 1 to 16- each pixel is made from this many samples squared, giving smoother edges
+    #[clap(short, long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=MAX_SAMPLES as i64))]
+    antialias: u8,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// show what's known about a flag, like who designed it and what it stands for
+    Info {
+        /// name of the flag, a path to a flag file, or "-" to read the flag from stdin
+        flag: String,
+    },
+
+    /// write a flag out in another format
+    Convert {
+        /// name of the flag, a path to a flag file, or "-" to read the flag from stdin
+        flag: String,
+
+        /// format to write the flag in- "yaml", "json" or "toml"
+        #[clap(long)]
+        to: FlagFormat,
+    },
+
+    /// find flags with a name, alias or tag like the given term
+    Search {
+        /// what to look for, like "bi" or "gender"
+        term: String,
+    },
+}
+
+/// explains what went wrong in a way that makes sense from the command line, then exits
+fn fail(err: PrideError) -> ! {
+    match err {
+        PrideError::InvalidNumber { what, value } =>
+            eprintln!("error parsing flag: {} \"{}\" should be a percentage, a fraction, or a positive number", what, value),
+        PrideError::UnknownFlag { name, suggestions } =>
+            eprintln!("flag {} doesn't exist! {}try \"--list-flags\" to list all available flags", name, did_you_mean(&suggestions)),
+        PrideError::ReadFlag { path, source } if source.kind() == ErrorKind::NotFound => {
+            // it might have been meant as a name rather than a path
+            let suggestions = path.file_stem().map(|name| suggest_flags(&name.to_string_lossy())).unwrap_or_default();
+            eprintln!("flag file {} doesn't exist! {}try \"--list-flags\" to list all available flags", path.display(), did_you_mean(&suggestions));
+        },
+        PrideError::ReadFlag { path, source } => eprintln!("error reading flag {}: {}", path.display(), source),
+        PrideError::Parse { format, source } => eprintln!("error parsing flag as {}: {}", format, source),
+        PrideError::Convert { format, source } => eprintln!("error converting flag to {}: {}", format, source),
+        PrideError::RendererOptions(err) =>
+            eprintln!("failed to parse renderer options: {} (try \"--renderer-options list\" to list all available renderer options)", err),
+        PrideError::InvalidRendererOption(reason) => eprintln!("failed to parse renderer options: {}", reason),
+        PrideError::LoadImage { path, source } => eprintln!("error loading flag image {}: {}", path.display(), source),
+        PrideError::Image(err) => eprintln!("error writing image: {}", err),
+        PrideError::Io(err) => eprintln!("error writing output: {}", err),
+        PrideError::TerminalSize(err) => eprintln!("couldn't get the terminal size (is stdout a terminal?): {}", err),
+
+        #[cfg(all(target_os = "linux", feature = "framebuffer"))]
+        PrideError::Framebuffer(err) => eprintln!("error using framebuffer: {}", err),
+    }
+
+    exit(1);
+}
+
+/// finds a flag by name or path, or reads it from stdin if given "-". its images are only loaded if they're going to
+/// be drawn, so flags with missing images can still be looked at and converted
+fn get_flag(name_or_path: &str, images: bool) -> Result<Flag, PrideError> {
+    if name_or_path != "-" {
+        return if images { load_flag(name_or_path) } else { read_flag(name_or_path) };
+    }
+
+    let text = io::read_to_string(stdin()).map_err(|source| PrideError::ReadFlag { path: "-".into(), source })?;
+    let flag = Flag::parse(&text)?;
+
+    if images { with_images(flag) } else { Ok(flag) }
+}
+
+/// loads the images of a flag that wasn't read from a file, relative to the current directory
+fn with_images(mut flag: Flag) -> Result<Flag, PrideError> {
+    flag.load_images(Path::new(""))?;
+    Ok(flag)
+}
+
+/// suggests flags that might have been meant instead, if there are any
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!("did you mean {}? ", quoted_list(suggestions))
+    }
+}
+
+/// gets a row of blocks in the colors of the given flag, or nothing if stdout isn't a terminal that can show them
+fn swatch(flag: &Flag) -> String {
+    if !stdout().is_terminal() {
+        return String::new();
+    }
+
+    flag.colors().iter()
+        .map(|color| format!("\x1b[48;2;{};{};{}m  ", color.red, color.green, color.blue))
+        .chain(["\x1b[0m".to_string()])
+        .collect()
+}
+
+/// list all available flags, along with their colors if they can be shown
+fn list_flags() {
+    let names = flag_names();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+
+    println!("available flags:");
+    for name in names {
+        let swatch = read_flag(&name).map(|flag| swatch(&flag)).unwrap_or_default();
+        println!("{}", format!("    - {:width$} {}", name, swatch, width = width).trim_end());
+    }
+}
+
+/// list flags matching a search term, along with their colors and full names
+fn search(term: &str) {
+    let flags = search_flags(term);
+
+    if flags.is_empty() {
+        eprintln!("no flags found matching \"{}\"! try \"--list-flags\" to list all available flags", term);
+        exit(1);
+    }
+
+    let width = flags.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    for (name, flag) in flags {
+        let details: Vec<String> = [swatch(&flag), flag.display_name.clone().unwrap_or_default()].into_iter()
+            .filter(|detail| !detail.is_empty())
+            .collect();
+
+        println!("{}", format!("    - {:width$} {}", name, details.join(" "), width = width).trim_end());
+    }
+}
+
+/// print everything that's known about a flag
+fn print_info(flag: &Flag) {
+    println!("{}", flag.display_name.as_ref().or(flag.name.as_ref()).map_or("unnamed flag", String::as_str));
+
+    let colors: Vec<String> = flag.colors().iter().map(Color::to_string).collect();
+    let fields = [
+        ("name", flag.name.clone()),
+        ("aliases", Some(flag.aliases.join(", "))),
+        ("designer", flag.designer.clone()),
+        ("year", flag.year.map(|year| year.to_string())),
+        ("source", flag.source.clone()),
+        ("tags", Some(flag.tags.join(", "))),
+        ("colors", Some(format!("{} {}", swatch(flag), colors.join(" ")))),
+    ];
+
+    // fields that aren't known are left out
+    for (label, value) in fields {
+        if let Some(value) = value.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+            println!("    {}: {}", label, value);
+        }
+    }
+
+    if let Some(description) = &flag.description {
+        println!();
+        println!("{}", description.trim());
+    }
 }
 
 fn main() {
     let args = Args::parse();
 
+    match &args.command {
+        Some(Command::Info { flag }) => {
+            print_info(&get_flag(flag, false).unwrap_or_else(|err| fail(err)));
+            return;
+        },
+        Some(Command::Convert { flag, to }) => {
+            let text = get_flag(flag, false).and_then(|flag| flag.to_string_as(*to)).unwrap_or_else(|err| fail(err));
+            println!("{}", text.trim_end());
+            return;
+        },
+        Some(Command::Search { term }) => {
+            search(term);
+            return;
+        },
+        None => (),
+    }
+
+    // list available flags if requested
+    if args.list_flags {
+        list_flags();
+        return;
+    }
+
+    // whether the renderer was picked by the user, rather than left to the default
+    let renderer_given = args.renderer.is_some();
+
     // get renderer name from args- default to value set in renderer/mod.rs
-    let renderer_name = args.renderer.unwrap_or_else(crate::render::default_renderer_name);
+    let renderer_name = args.renderer.unwrap_or_else(pride::render::default_renderer_name);
 
     // list available renderers if requested
     if renderer_name == *"list" {
@@ -68,20 +267,30 @@ fn main() {
         }
     }
 
-    // create a new renderer
-    let mut renderer = create_renderer(renderer_name, &format!("{{{}}}", args.renderer_options.unwrap_or_else(|| "".to_string())));
-
-    // read flag from file
-    let flag = fs::read_to_string(args.flag).unwrap();
-
-    // convert yaml to our flag struct
-    let flag: Flag = match serde_yaml::from_str(&flag) {
-        Ok(flag) => flag,
-        Err(err) => {
-            eprintln!("error parsing flag: {}", err);
+    // find the flag, reading it along with any images next to it if it's a file
+    let flag = match (args.flag.as_deref(), args.flag_yaml.as_deref()) {
+        (_, Some(yaml)) => Flag::from_yaml(yaml).and_then(with_images),
+        (Some(flag), None) => get_flag(flag, true),
+        (None, None) => {
+            eprintln!("no flag given! pass a flag's name or path with \"--flag\" or its YAML with \"--flag-yaml\", or try \"--list-flags\" to list all available flags");
             exit(1);
         },
-    };
+    }.unwrap_or_else(|err| fail(err));
+
+    let renderer_options = format!("{{{}}}", args.renderer_options.unwrap_or_else(|| "".to_string()));
+
+    // create a new renderer, letting the user know which one was picked if they asked for it to be picked for them
+    let mut created = create_renderer(renderer_name, &renderer_options).unwrap_or_else(|err| fail(err));
+
+    for option in &created.ignored_options {
+        eprintln!("renderer {:?} doesn't have option \"{}\", ignoring it", created.name, option);
+    }
+
+    if renderer_given && created.name != renderer_name {
+        eprintln!("using renderer {:?}", created.name);
+    }
 
-    render_flag(&mut renderer, &flag, args.background);
+    if let Err(err) = flag.render(&mut created.renderer, args.background, args.antialias.into()) {
+        fail(err);
+    }
 }
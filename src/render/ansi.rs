@@ -1,25 +1,131 @@
 //! renderer that renders directly to the terminal with ANSI escape sequences
 
-use crate::bitmap::Bitmap;
+use crate::bitmap::{Bitmap, Color};
+use crate::error::PrideError;
 use serde::{Serialize, Deserialize};
-use std::{
-    cmp::min,
-    io::{Write, stdin, stdout},
-};
-use super::Renderer;
-use termion::{
-    color, cursor, clear,
-    event::Event,
-    input::TermRead,
-    raw::IntoRawMode,
-    screen::AlternateScreen,
+use std::{cmp::min, fmt};
+use super::{
+    Renderer,
+    palette::{is_light, nearest_xterm},
+    terminal::{display, terminal_size},
 };
+use termion::{color, cursor, clear, style};
+
+const BLACK: Color = Color::new(0, 0, 0);
+const WHITE: Color = Color::new(255, 255, 255);
+
+/// characters used to divide each cell into smaller pixels
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Glyphs {
+    /// upper half block, giving 1x2 pixels per cell
+    #[default]
+    Half,
+
+    /// quadrant blocks, giving 2x2 pixels per cell
+    Quadrant,
+
+    /// sextant blocks from Unicode 13, giving 2x3 pixels per cell
+    Sextant,
+
+    /// braille patterns, giving 2x4 dots per cell. only the foreground color is used
+    Braille,
+}
+
+impl Glyphs {
+    /// number of pixels in each cell, horizontally and vertically
+    fn cell_size(&self) -> (usize, usize) {
+        match self {
+            Self::Half => (1, 2),
+            Self::Quadrant => (2, 2),
+            Self::Sextant => (2, 3),
+            Self::Braille => (2, 4),
+        }
+    }
+
+    /// gets the character that draws the pixels set in the mask with the foreground color.
+    /// pixels are numbered from left to right, then top to bottom
+    fn glyph(&self, mask: usize) -> char {
+        match self {
+            Self::Half => [' ', '\u{2580}', '\u{2584}', '\u{2588}'][mask],
+            Self::Quadrant => [
+                ' ', '\u{2598}', '\u{259d}', '\u{2580}', '\u{2596}', '\u{258c}', '\u{259e}', '\u{259b}',
+                '\u{2597}', '\u{259a}', '\u{2590}', '\u{259c}', '\u{2584}', '\u{2599}', '\u{259f}', '\u{2588}',
+            ][mask],
+            Self::Sextant => match mask {
+                0 => ' ',
+                0b010101 => '\u{258c}',
+                0b101010 => '\u{2590}',
+                0b111111 => '\u{2588}',
+                // the sextant block is in order, minus the patterns that already exist as half blocks
+                _ => char::from_u32(0x1fb00 + mask as u32 - 1 - (mask > 0b010101) as u32 - (mask > 0b101010) as u32).unwrap(),
+            },
+            Self::Braille => {
+                // braille dots are numbered down the left column first, with the bottom row added on afterwards
+                const DOTS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+                let dots = DOTS.iter().enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .fold(0, |dots, (_, dot)| dots | dot);
+
+                char::from_u32(0x2800 + dots).unwrap()
+            },
+        }
+    }
+
+    /// whether the background color is used to draw the cell
+    fn uses_background(&self) -> bool {
+        !matches!(self, Self::Braille)
+    }
+}
+
+/// how many colors the terminal can display
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum ColorDepth {
+    /// 24-bit color
+    #[serde(rename = "truecolor")]
+    TrueColor,
+
+    /// the xterm 256 color palette
+    #[default]
+    #[serde(rename = "256")]
+    Ansi256,
+
+    /// the 8 standard colors and their bright variants
+    #[serde(rename = "16")]
+    Ansi16,
+
+    /// only the 8 standard colors
+    #[serde(rename = "8")]
+    Ansi8,
+
+    /// no colors at all, just the terminal's default foreground and background
+    #[serde(rename = "mono")]
+    Mono,
+}
+
+impl fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TrueColor => write!(f, "truecolor"),
+            Self::Ansi256 => write!(f, "256"),
+            Self::Ansi16 => write!(f, "16"),
+            Self::Ansi8 => write!(f, "8"),
+            Self::Mono => write!(f, "mono"),
+        }
+    }
+}
 
 /// options for ANSI renderer
 #[derive(Serialize, Deserialize, Default)]
 pub struct AnsiRendererOptions {
+    /// how many colors to draw with
+    #[serde(default)]
+    pub color_depth: ColorDepth,
+
+    /// characters to draw with
     #[serde(default)]
-    pub true_color: bool,
+    pub glyphs: Glyphs,
 }
 
 /// renderer that renders directly to the terminal
@@ -28,25 +134,67 @@ pub struct AnsiRenderer {
 }
 
 impl AnsiRenderer {
-    pub fn new(options: &str) -> Self {
-        Self {
-            options: match serde_yaml::from_str(options) {
-                Ok(options) => options,
-                Err(err) => {
-                    eprintln!("failed to parse renderer options: {err}");
-                    std::process::exit(1);
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
+    }
+
+    /// converts a color into an ANSI escape sequence for the foreground color
+    fn fg_string(&self, c: Color) -> String {
+        self.color_string(c, false)
+    }
+
+    /// converts a color into an ANSI escape sequence for the background color
+    fn bg_string(&self, c: Color) -> String {
+        self.color_string(c, true)
+    }
+
+    /// converts a color into an ANSI escape sequence for the current color depth
+    fn color_string(&self, c: Color, background: bool) -> String {
+        // SGR parameter for the first of the 8 standard colors
+        let base = if background { 40 } else { 30 };
+
+        match self.options.color_depth {
+            ColorDepth::TrueColor => if background {
+                color::Rgb(c.red, c.green, c.blue).bg_string()
+            } else {
+                color::Rgb(c.red, c.green, c.blue).fg_string()
+            },
+            ColorDepth::Ansi256 => {
+                // pick the closest looking color out of the 256 color palette
+                let value = color::AnsiValue(nearest_xterm(c, 256));
+                if background { value.bg_string() } else { value.fg_string() }
+            },
+            ColorDepth::Ansi16 => {
+                // bright colors have their own SGR parameters rather than going through the 256 color palette,
+                // since terminals that only have 16 colors won't understand those
+                let value = nearest_xterm(c, 16);
+                if value < 8 {
+                    format!("\x1b[{}m", base + value)
+                } else {
+                    format!("\x1b[{}m", base + 60 + value - 8)
                 }
             },
+            ColorDepth::Ansi8 => format!("\x1b[{}m", base + nearest_xterm(c, 8)),
+            ColorDepth::Mono => if background {
+                // the background is taken care of by reversing the foreground
+                String::new()
+            } else if c == BLACK {
+                // swap the terminal's foreground and background so the glyph is drawn dark on light
+                style::Invert.to_string()
+            } else {
+                style::NoInvert.to_string()
+            },
         }
     }
 }
 
 impl Renderer for AnsiRenderer {
     /// draws a bitmap to the terminal with ANSI escape codes
-    fn render(&mut self, bitmap: &Bitmap) {
-        assert!(bitmap.height % 2 == 0, "bitmap height is not an even number");
-
-        let (term_width, term_height) = self.get_size();
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError> {
+        let (term_width, term_height) = self.get_size()?;
+        let (cell_width, cell_height) = self.options.glyphs.cell_size();
 
         let mut sequence = String::new();
 
@@ -55,101 +203,155 @@ impl Renderer for AnsiRenderer {
         sequence.push_str(clear::All.as_ref());
 
         // last color values- used to speed up drawing since we can skip escape sequences for duplicates
-        let mut last_upper_color: Option<String> = None;
-        let mut last_lower_color: Option<String> = None;
+        let mut last_fg_color: Option<String> = None;
+        let mut last_bg_color: Option<String> = None;
+
+        // pixels of the current cell
+        let mut pixels = Vec::with_capacity(cell_width * cell_height);
 
         // convert bitmap to text characters and ANSI escape codes
-        for y in (0..min(bitmap.height, term_height)).step_by(2) {
+        for y in (0..min(bitmap.height, term_height)).step_by(cell_height) {
             // move cursor to start of line
-            sequence.push_str(&cursor::Goto(1, (y / 2 + 1).try_into().unwrap()).to_string());
+            sequence.push_str(&cursor::Goto(1, (y / cell_height + 1).try_into().unwrap()).to_string());
 
-            for x in 0..min(bitmap.width, term_width) {
-                // we're dividing each character cell vertically into two colors
-                let upper_color = bitmap.get(x, y).unwrap();
-                let lower_color = bitmap.get(x, y + 1).unwrap();
+            for x in (0..min(bitmap.width, term_width)).step_by(cell_width) {
+                // collect all the pixels that make up this cell
+                pixels.clear();
+                for y2 in y..y + cell_height {
+                    for x2 in x..x + cell_width {
+                        let pixel = bitmap.get(x2, y2).unwrap_or(BLACK);
 
-                // escape sequence strings for upper and lower colors of character cell (foreground and background with a special character)
-                let upper_color_str;
-                let lower_color_str;
+                        // without colors, a pixel is either lit or not
+                        if self.options.color_depth == ColorDepth::Mono {
+                            pixels.push(if is_light(pixel) { WHITE } else { BLACK });
+                        } else {
+                            pixels.push(pixel);
+                        }
+                    }
+                }
 
-                // convert colors into ANSI escape sequences
-                if self.options.true_color {
-                    upper_color_str = color::Rgb(upper_color.red, upper_color.green, upper_color.blue).fg_string();
-                    lower_color_str = color::Rgb(lower_color.red, lower_color.green, lower_color.blue).bg_string();
+                // a cell can only show two colors, so pick the two that fit best
+                let (fg_color, bg_color, mask) = if self.options.glyphs.uses_background() {
+                    approximate(&pixels)
                 } else {
-                    // alias to 216 colors
-                    upper_color_str = color::AnsiValue::rgb(((upper_color.red as f64 / 256.0) * 5.0) as u8, ((upper_color.green as f64 / 256.0) * 5.0) as u8, ((upper_color.blue as f64 / 256.0) * 5.0) as u8).fg_string();
-                    lower_color_str = color::AnsiValue::rgb(((lower_color.red as f64 / 256.0) * 5.0) as u8, ((lower_color.green as f64 / 256.0) * 5.0) as u8, ((lower_color.blue as f64 / 256.0) * 5.0) as u8).bg_string();
-                }
+                    let (color, mask) = dominant(&pixels);
+
+                    // the foreground can't be dark without colors, so light up the other dots instead
+                    if self.options.color_depth == ColorDepth::Mono && color == BLACK {
+                        (WHITE, WHITE, !mask & ((1 << pixels.len()) - 1))
+                    } else {
+                        (color, color, mask)
+                    }
+                };
+
+                // escape sequence strings for foreground and background colors of character cell
+                let fg_color_str = self.fg_string(fg_color);
 
                 // add colors to sequence if they've changed at all
-                if if let Some(last) = &last_upper_color { last != &upper_color_str } else { true } {
-                    sequence.push_str(&upper_color_str);
+                if if let Some(last) = &last_fg_color { last != &fg_color_str } else { true } {
+                    sequence.push_str(&fg_color_str);
                 }
 
-                if if let Some(last) = &last_lower_color { last != &lower_color_str } else { true } {
-                    sequence.push_str(&lower_color_str);
-                }
+                last_fg_color = Some(fg_color_str);
+
+                if self.options.glyphs.uses_background() {
+                    let bg_color_str = self.bg_string(bg_color);
 
-                // set last colors to current colors
-                last_upper_color = Some(upper_color_str);
-                last_lower_color = Some(lower_color_str);
+                    if if let Some(last) = &last_bg_color { last != &bg_color_str } else { true } {
+                        sequence.push_str(&bg_color_str);
+                    }
+
+                    last_bg_color = Some(bg_color_str);
+                }
 
                 // lastly, write the character for the cell
-                sequence.push('\u{2580}');
+                sequence.push(self.options.glyphs.glyph(mask));
             }
         }
 
-        let stdin = stdin();
-        // create an alternate terminal buffer to write to so we can have a cleaner switch back
-        let mut stdout = AlternateScreen::from(stdout().into_raw_mode().unwrap()); // stdout needs to be set to raw mode to read individual characters
+        Ok(display(&sequence)?)
+    }
 
-        // put everything on screen
-        if let Err(err) = write!(stdout, "{}", sequence) {
-            reset_terminal();
-            eprintln!("{}error writing to stdout: {}", color::Fg(color::Red), err);
-            return;
-        }
+    /// gets max size of renderer
+    fn get_size(&self) -> Result<(usize, usize), PrideError> {
+        let (width, height) = terminal_size()?;
+        let (cell_width, cell_height) = self.options.glyphs.cell_size();
+
+        Ok((width as usize * cell_width, height as usize * cell_height))
+    }
+}
+
+/// squared distance between two colors
+fn distance(a: Color, b: Color) -> u32 {
+    let red = a.red as i32 - b.red as i32;
+    let green = a.green as i32 - b.green as i32;
+    let blue = a.blue as i32 - b.blue as i32;
+    (red * red + green * green + blue * blue) as u32
+}
+
+/// picks the foreground and background colors that best approximate a cell's pixels, along with a mask of
+/// which pixels use the foreground color. the first pixel always uses the foreground color
+fn approximate(pixels: &[Color]) -> (Color, Color, usize) {
+    let all = (1 << pixels.len()) - 1;
 
-        if let Err(err) = stdout.flush() {
-            reset_terminal();
-            eprintln!("{}error flushing stdout: {}", color::Fg(color::Red), err);
-            return;
+    // find the distinct colors in the cell
+    let mut colors: Vec<Color> = Vec::with_capacity(pixels.len());
+    for pixel in pixels.iter() {
+        if !colors.contains(pixel) {
+            colors.push(*pixel);
         }
+    }
+
+    if colors.len() == 1 {
+        return (colors[0], colors[0], all);
+    }
+
+    // try every pair of colors, keeping whichever has the least error
+    let mut best = (u32::MAX, colors[0], colors[1]);
+
+    for (i, a) in colors.iter().enumerate() {
+        for b in colors[i + 1..].iter() {
+            let error = pixels.iter().map(|p| distance(*p, *a).min(distance(*p, *b))).sum();
 
-        // wait for a key to be pressed before exiting
-        for evt in stdin.events() {
-            match evt {
-                Ok(evt) => if let Event::Key(_) = evt { break; }, // break out of loop if we get a key event
-                Err(err) => {
-                    reset_terminal();
-                    eprintln!("{}error reading stdin: {}", color::Fg(color::Red), err);
-                    return;
-                },
+            if error < best.0 {
+                best = (error, *a, *b);
             }
         }
-
-        reset_terminal(); // terminal is reset just in case we don't support alternate buffers
     }
 
-    /// gets max size of renderer
-    fn get_size(&self) -> (usize, usize) {
-        let (width, height) = termion::terminal_size().unwrap();
-        
-        (width as usize, height as usize * 2)
+    let (_, mut fg, mut bg) = best;
+
+    // the first pixel decides which color is the foreground
+    if distance(pixels[0], bg) < distance(pixels[0], fg) {
+        std::mem::swap(&mut fg, &mut bg);
     }
+
+    let mask = pixels.iter().enumerate()
+        .filter(|(_, p)| distance(**p, fg) <= distance(**p, bg))
+        .fold(0, |mask, (i, _)| mask | (1 << i));
+
+    (fg, bg, mask)
 }
 
-/// revert any changes we've made while rendering
-fn reset_terminal() {
-    let mut stdout = stdout();
-    let sequence = format!("{}{}{}", color::Fg(color::Reset), color::Bg(color::Reset), cursor::Show);
+/// picks the most common color in a cell, along with a mask of which pixels are close enough to it to be drawn
+fn dominant(pixels: &[Color]) -> (Color, usize) {
+    /// how far away a pixel's color can be from the dominant color and still be drawn
+    const THRESHOLD: u32 = 48 * 48 * 3;
 
-    if write!(stdout, "{}", sequence).is_err() { // attempt to recover if an error occurs
-        print!("{}", sequence);
+    let mut counts: Vec<(Color, usize)> = Vec::with_capacity(pixels.len());
+    for pixel in pixels.iter() {
+        match counts.iter_mut().find(|(color, _)| color == pixel) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*pixel, 1)),
+        }
     }
 
-    if stdout.flush().is_err() {
-        print!("{}", sequence);
-    }
+    // ties go to whichever color comes first
+    let color = counts.iter().fold(counts[0], |best, next| if next.1 > best.1 { *next } else { best }).0;
+
+    let mask = pixels.iter().enumerate()
+        .filter(|(_, p)| distance(**p, color) <= THRESHOLD)
+        .fold(0, |mask, (i, _)| mask | (1 << i));
+
+    (color, mask)
 }
@@ -0,0 +1,90 @@
+//! detection of what the terminal we're running in is capable of
+
+use std::{env, time::Duration};
+use super::{
+    ansi::ColorDepth,
+    terminal::query,
+};
+
+/// how long to wait for the terminal to answer queries
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// what the terminal is capable of
+#[derive(Debug, Default)]
+pub struct Capabilities {
+    /// how many colors text can be drawn with
+    pub color_depth: ColorDepth,
+
+    /// whether the kitty graphics protocol is supported
+    pub kitty: bool,
+
+    /// whether sixel graphics are supported
+    pub sixel: bool,
+
+    /// whether iTerm2's inline images are supported
+    pub iterm: bool,
+
+    /// whether we're running in the linux virtual console
+    pub linux_console: bool,
+}
+
+/// works out what the terminal is capable of from the environment and by asking the terminal itself
+pub fn detect() -> Capabilities {
+    let term = env::var("TERM").unwrap_or_default();
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    let mut capabilities = Capabilities {
+        linux_console: term == "linux",
+        iterm: term_program == "iTerm.app" || term_program == "WezTerm" || env::var("LC_TERMINAL").map(|t| t == "iTerm2").unwrap_or(false),
+        ..Default::default()
+    };
+
+    capabilities.color_depth = if colorterm == "truecolor" || colorterm == "24bit" {
+        ColorDepth::TrueColor
+    } else if term.is_empty() || term == "dumb" {
+        ColorDepth::Mono
+    } else if capabilities.linux_console || term.ends_with("-16color") {
+        ColorDepth::Ansi16
+    } else if term.ends_with("-8color") || term == "vt100" || term == "ansi" {
+        ColorDepth::Ansi8
+    } else {
+        ColorDepth::Ansi256
+    };
+
+    // the linux console doesn't do graphics, so there's no point waiting on it
+    if !capabilities.linux_console {
+        query_graphics(&mut capabilities);
+    }
+
+    capabilities
+}
+
+/// asks the terminal which graphics protocols it supports
+fn query_graphics(capabilities: &mut Capabilities) {
+    // a kitty graphics query for a tiny image, followed by a primary device attributes (DA1) request.
+    // every terminal answers DA1, so its response tells us when to stop waiting for the kitty response
+    let request = "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[c";
+
+    let response = match query(request, is_da1_complete, QUERY_TIMEOUT) {
+        Some(response) => String::from_utf8_lossy(&response).to_string(),
+        None => return,
+    };
+
+    capabilities.kitty = response.contains("\x1b_Gi=31;OK");
+
+    // DA1 responses look like CSI ? 62 ; 4 ; 22 c, where 4 means sixel graphics are supported
+    if let Some(start) = response.rfind("\x1b[?") {
+        capabilities.sixel = response[start + 3..].trim_end_matches('c').split(';').any(|param| param == "4");
+    }
+}
+
+/// checks whether a response ends with a complete DA1 response
+fn is_da1_complete(response: &[u8]) -> bool {
+    let response = String::from_utf8_lossy(response);
+
+    match response.rfind("\x1b[?") {
+        Some(start) => response[start..].ends_with('c'),
+        None => false,
+    }
+}
@@ -1,19 +1,15 @@
 //! renderer that writes to a linux framebuffer
 
 use crate::bitmap::{Bitmap, Color};
+use crate::error::PrideError;
 use framebuffer::{Framebuffer, KdMode};
 use serde::{Serialize, Deserialize};
 use std::{
-    io::{Write, stdin, stdout},
+    io::{Write, stdout},
     path::PathBuf,
 };
-use super::Renderer;
-use termion::{
-    color,
-    event::Event,
-    input::TermRead,
-    raw::IntoRawMode,
-};
+use super::{Renderer, terminal::wait_for_key};
+use termion::raw::IntoRawMode;
 
 /// options for image renderer
 #[derive(Serialize, Deserialize)]
@@ -39,23 +35,17 @@ pub struct FramebufferRenderer {
 
 impl FramebufferRenderer {
     /// create a new FramebufferRenderer with the given options
-    pub fn new(options: &str) -> Self {
-        Self {
-            options: match serde_yaml::from_str(options) {
-                Ok(options) => options,
-                Err(err) => {
-                    eprintln!("failed to parse renderer options: {err}");
-                    std::process::exit(1);
-                }
-            },
-        }
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
     }
 }
 
 impl Renderer for FramebufferRenderer {
-    fn render(&mut self, bitmap: &Bitmap) {
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError> {
         // open framebuffer
-        let mut framebuffer = Framebuffer::new(&self.options.device).unwrap();
+        let mut framebuffer = Framebuffer::new(&self.options.device)?;
 
         // get framebuffer info
         let height = framebuffer.var_screen_info.yres;
@@ -68,7 +58,7 @@ impl Renderer for FramebufferRenderer {
         // copy image to temporaray buffer
         for (y, line) in frame.chunks_mut(line_length as usize).enumerate() {
             for (x, p) in line.chunks_mut(bytes_per_pixel as usize).enumerate() {
-                let pixel = bitmap.get(x as usize, y as usize).unwrap_or(Color::new(0, 0, 0));
+                let pixel = bitmap.get(x, y).unwrap_or(Color::new(0, 0, 0));
 
                 p[0] = pixel.blue;
                 p[1] = pixel.green;
@@ -77,36 +67,36 @@ impl Renderer for FramebufferRenderer {
         }
 
         // set tty to graphics mode
-        let _ = Framebuffer::set_kd_mode(KdMode::Graphics).unwrap();
+        Framebuffer::set_kd_mode(KdMode::Graphics)?;
 
         // draw image
         framebuffer.write_frame(&frame);
 
-        let stdin = stdin();
-        let mut stdout = stdout().into_raw_mode().unwrap(); // stdout needs to be set to raw mode to read individual characters
-		
-        // we need to flush stdout for this to work
-        stdout.flush().unwrap();
+        // stdout needs to be set to raw mode to read individual characters, and flushed for that to work
+        let raw = stdout().into_raw_mode().and_then(|mut stdout| stdout.flush().map(|_| stdout));
+        let _stdout = match raw {
+            Ok(stdout) => stdout,
+            Err(err) => {
+                Framebuffer::set_kd_mode(KdMode::Text)?;
+                return Err(err.into());
+            },
+        };
 
         // wait for a key to be pressed before exiting
-        for evt in stdin.events() {
-            match evt {
-                Ok(evt) => if let Event::Key(_) = evt { break; }, // break out of loop if we get a key event
-                Err(err) => {
-                    let _ = Framebuffer::set_kd_mode(KdMode::Text).unwrap();
-                    eprintln!("{}error reading stdin: {}", color::Fg(color::Red), err);
-                    return;
-                },
-            }
+        if let Err(err) = wait_for_key() {
+            Framebuffer::set_kd_mode(KdMode::Text)?;
+            return Err(err.into());
         }
 
         // switch back to text mode
-        let _ = Framebuffer::set_kd_mode(KdMode::Text).unwrap();
+        Framebuffer::set_kd_mode(KdMode::Text)?;
+
+        Ok(())
     }
 
-    fn get_size(&self) -> (usize, usize) {
-        let framebuffer = Framebuffer::new(&self.options.device).unwrap();
+    fn get_size(&self) -> Result<(usize, usize), PrideError> {
+        let framebuffer = Framebuffer::new(&self.options.device)?;
 
-        (framebuffer.var_screen_info.xres as usize, framebuffer.var_screen_info.yres as usize)
+        Ok((framebuffer.var_screen_info.xres as usize, framebuffer.var_screen_info.yres as usize))
     }
 }
@@ -1,7 +1,8 @@
 //! renderer that writes to an image
 
-use crate::bitmap::{Bitmap, Color};
-use image::ImageBuffer;
+use crate::bitmap::Bitmap;
+use crate::error::PrideError;
+use image::{DynamicImage, ImageFormat};
 use serde::{Serialize, Deserialize};
 use std::path::PathBuf;
 use super::Renderer;
@@ -41,32 +42,29 @@ pub struct ImageRenderer {
 
 impl ImageRenderer {
     /// create a new ImageRenderer with the given options
-    pub fn new(options: &str) -> Self {
-        Self {
-            options: match serde_yaml::from_str(options) {
-                Ok(options) => options,
-                Err(err) => {
-                    eprintln!("failed to parse renderer options: {err}");
-                    std::process::exit(1);
-                }
-            },
-        }
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
     }
 }
 
 impl Renderer for ImageRenderer {
-    fn render(&mut self, bitmap: &Bitmap) {
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError> {
         // convert internal bitmap format to ImageBuffer
-        let img = ImageBuffer::from_fn(self.options.width, self.options.height, |x, y| {
-            let pixel = bitmap.get(x as usize, y as usize).unwrap_or(Color::new(0, 0, 0));
-            image::Rgb([pixel.red, pixel.green, pixel.blue])
-        });
+        let img = DynamicImage::from(bitmap.to_rgba_image(self.options.width, self.options.height));
+
+        // formats without an alpha channel get the colors as they are
+        let img = match ImageFormat::from_path(&self.options.output) {
+            Ok(ImageFormat::Jpeg | ImageFormat::Pnm) => DynamicImage::from(img.to_rgb8()),
+            _ => img,
+        };
 
         // save image
-        img.save(&self.options.output).unwrap();
+        Ok(img.save(&self.options.output)?)
     }
 
-    fn get_size(&self) -> (usize, usize) {
-        (self.options.width as usize, self.options.height as usize)
+    fn get_size(&self) -> Result<(usize, usize), PrideError> {
+        Ok((self.options.width as usize, self.options.height as usize))
     }
 }
@@ -0,0 +1,102 @@
+//! renderer that draws to the terminal with iTerm2's inline image protocol (also supported by WezTerm)
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use crate::bitmap::Bitmap;
+use crate::error::PrideError;
+use image::ImageOutputFormat;
+use serde::{Serialize, Deserialize};
+use std::{fmt::Write, io::Cursor};
+use super::{
+    Renderer,
+    terminal::{cell_size, display, terminal_size},
+};
+use termion::{cursor, clear};
+
+/// options for iTerm2 renderer
+#[derive(Serialize, Deserialize)]
+pub struct ItermRendererOptions {
+    /// width of the image in character cells- defaults to the width of the terminal
+    #[serde(default)]
+    pub width: Option<u16>,
+
+    /// height of the image in character cells- defaults to the height of the terminal
+    #[serde(default)]
+    pub height: Option<u16>,
+
+    /// whether the terminal should keep the aspect ratio of the image when scaling it
+    #[serde(default = "default_preserve_aspect")]
+    pub preserve_aspect: bool,
+}
+
+fn default_preserve_aspect() -> bool { true }
+
+impl Default for ItermRendererOptions {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            preserve_aspect: default_preserve_aspect(),
+        }
+    }
+}
+
+/// renderer that renders to the terminal with inline images
+pub struct ItermRenderer {
+    pub options: ItermRendererOptions,
+}
+
+impl ItermRenderer {
+    /// create a new ItermRenderer with the given options
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
+    }
+
+    /// gets the size of the image in character cells
+    fn get_cells(&self) -> Result<(u16, u16), PrideError> {
+        let (width, height) = terminal_size()?;
+
+        // leave the last line free so the terminal doesn't scroll once the image is drawn
+        Ok((self.options.width.unwrap_or(width), self.options.height.unwrap_or(height.saturating_sub(1).max(1))))
+    }
+}
+
+impl Renderer for ItermRenderer {
+    /// encodes a bitmap as a PNG and sends it to the terminal
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError> {
+        // encode bitmap as PNG in memory
+        let img = bitmap.to_rgb_image(bitmap.width as u32, bitmap.height as u32);
+        let mut png = Cursor::new(Vec::new());
+
+        img.write_to(&mut png, ImageOutputFormat::Png)?;
+
+        let png = png.into_inner();
+        let (width, height) = self.get_cells()?;
+
+        let mut sequence = String::new();
+
+        // clear screen
+        sequence.push_str(cursor::Hide.as_ref());
+        sequence.push_str(clear::All.as_ref());
+        sequence.push_str(&cursor::Goto(1, 1).to_string());
+
+        // send the image with OSC 1337
+        write!(
+            sequence, "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio={}:{}\x07",
+            png.len(), width, height,
+            if self.options.preserve_aspect { 1 } else { 0 },
+            STANDARD.encode(&png),
+        ).unwrap();
+
+        Ok(display(&sequence)?)
+    }
+
+    /// gets max size of renderer
+    fn get_size(&self) -> Result<(usize, usize), PrideError> {
+        let (width, height) = self.get_cells()?;
+        let (cell_width, cell_height) = cell_size()?;
+
+        Ok((width as usize * cell_width, height as usize * cell_height))
+    }
+}
@@ -0,0 +1,89 @@
+//! renderer that draws to the terminal with the kitty graphics protocol
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use crate::bitmap::Bitmap;
+use crate::error::PrideError;
+use serde::{Serialize, Deserialize};
+use std::fmt::Write;
+use super::{
+    Renderer,
+    terminal::{display_with_cleanup, pixel_size},
+};
+use termion::{cursor, clear};
+
+/// maximum size of a single chunk of image data, as defined by the protocol
+const CHUNK_SIZE: usize = 4096;
+
+/// options for kitty renderer
+#[derive(Serialize, Deserialize, Default)]
+pub struct KittyRendererOptions {
+    /// id to give the image- picked automatically if left at 0
+    #[serde(default)]
+    pub id: u32,
+}
+
+/// renderer that renders to the terminal with the kitty graphics protocol
+pub struct KittyRenderer {
+    pub options: KittyRendererOptions,
+}
+
+impl KittyRenderer {
+    /// create a new KittyRenderer with the given options
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
+    }
+}
+
+impl Renderer for KittyRenderer {
+    /// sends a bitmap to the terminal as raw RGB data
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError> {
+        // use our pid so we're unlikely to clash with other programs' images
+        let id = if self.options.id == 0 { std::process::id().max(1) } else { self.options.id };
+
+        // flatten bitmap into RGB bytes
+        let mut data = Vec::with_capacity(bitmap.width * bitmap.height * 3);
+        for row in bitmap.data.iter() {
+            for color in row.iter() {
+                data.extend_from_slice(&[color.red, color.green, color.blue]);
+            }
+        }
+
+        let data = STANDARD.encode(data);
+
+        let mut sequence = String::new();
+
+        // clear screen
+        sequence.push_str(cursor::Hide.as_ref());
+        sequence.push_str(clear::All.as_ref());
+        sequence.push_str(&cursor::Goto(1, 1).to_string());
+
+        // transmit and display the image at the cursor in chunks. the cursor is left where it is (C=1)
+        // so the terminal won't scroll if the image reaches the bottom
+        let chunks: Vec<&[u8]> = data.as_bytes().chunks(CHUNK_SIZE).collect();
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = if i + 1 < chunks.len() { 1 } else { 0 };
+
+            if i == 0 {
+                write!(sequence, "\x1b_Ga=T,f=24,s={},v={},i={},C=1,q=2,m={};", bitmap.width, bitmap.height, id, more).unwrap();
+            } else {
+                write!(sequence, "\x1b_Gm={};", more).unwrap();
+            }
+
+            sequence.push_str(std::str::from_utf8(chunk).unwrap()); // base64 is always valid utf-8
+            sequence.push_str("\x1b\\");
+        }
+
+        // delete the image and free its data once we're done
+        let cleanup = format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", id);
+
+        Ok(display_with_cleanup(&sequence, &cleanup)?)
+    }
+
+    /// gets max size of renderer
+    fn get_size(&self) -> Result<(usize, usize), PrideError> {
+        pixel_size()
+    }
+}
@@ -1,36 +1,95 @@
 //! renderer trait and renderer implementations
 
-pub mod ansi;
+pub mod pdf;
+pub mod svg;
+
+#[cfg(feature = "image-output")]
 pub mod image;
 
-#[cfg(target_os = "linux")]
+#[cfg(feature = "ansi")]
+pub mod ansi;
+#[cfg(feature = "ansi")]
+pub mod detect;
+#[cfg(feature = "ansi")]
+pub mod iterm;
+#[cfg(feature = "ansi")]
+pub mod kitty;
+#[cfg(feature = "ansi")]
+pub mod palette;
+#[cfg(feature = "ansi")]
+pub mod sixel;
+#[cfg(any(feature = "ansi", feature = "framebuffer"))]
+pub mod terminal;
+
+#[cfg(all(target_os = "linux", feature = "framebuffer"))]
 pub mod framebuffer;
 
-use self::ansi::{AnsiRenderer, AnsiRendererOptions};
 use crate::bitmap::Bitmap;
+use crate::error::PrideError;
+use crate::shape::Drawing;
 use enum_iterator::{all, Sequence};
-use self::image::{ImageRenderer, ImageRendererOptions};
+use self::pdf::{PdfRenderer, PdfRendererOptions};
+use self::svg::{SvgRenderer, SvgRendererOptions};
 use std::str::FromStr;
 
-#[cfg(target_os = "linux")]
+#[cfg(feature = "ansi")]
+use self::{
+    ansi::{AnsiRenderer, AnsiRendererOptions},
+    detect::{Capabilities, detect},
+    iterm::{ItermRenderer, ItermRendererOptions},
+    kitty::{KittyRenderer, KittyRendererOptions},
+    sixel::{SixelRenderer, SixelRendererOptions},
+};
+
+#[cfg(feature = "image-output")]
+use self::image::{ImageRenderer, ImageRendererOptions};
+
+#[cfg(all(target_os = "linux", feature = "framebuffer"))]
 use self::framebuffer::{FramebufferRenderer, FramebufferRendererOptions};
 
 /// describes how the rest of the program should interact with renderers
 pub trait Renderer {
     /// render the specified bitmap
-    fn render(&mut self, bitmap: &Bitmap);
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError>;
 
     /// get size of renderer
-    fn get_size(&self) -> (usize, usize);
+    fn get_size(&self) -> Result<(usize, usize), PrideError>;
+}
+
+/// renderers that take shapes directly instead of a bitmap, for resolution independent output
+pub trait VectorRenderer {
+    /// render the specified drawing
+    fn render(&mut self, drawing: &Drawing) -> Result<(), PrideError>;
+
+    /// get size of renderer for a flag with the given aspect ratio
+    fn get_size(&self, aspect: f64) -> (f64, f64);
+}
+
+/// a renderer of either kind, as returned by create_renderer
+pub enum AnyRenderer {
+    Raster(Box<dyn Renderer>),
+    Vector(Box<dyn VectorRenderer>),
 }
 
 /// list of all available renderers
-#[derive(Debug, Sequence)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Sequence)]
 pub enum Renderers {
+    #[cfg(feature = "ansi")]
+    Auto,
+    #[cfg(feature = "ansi")]
     Ansi,
+    #[cfg(feature = "image-output")]
     Image,
+    #[cfg(feature = "ansi")]
+    Sixel,
+    #[cfg(feature = "ansi")]
+    Kitty,
+    #[cfg(feature = "ansi")]
+    Iterm,
+    Svg,
+    Pdf,
 
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", feature = "framebuffer"))]
     Framebuffer,
 }
 
@@ -39,10 +98,22 @@ impl FromStr for Renderers {
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         match input.to_lowercase().as_ref() {
+            #[cfg(feature = "ansi")]
+            "auto" => Ok(Self::Auto),
+            #[cfg(feature = "ansi")]
             "ansi" => Ok(Self::Ansi),
+            #[cfg(feature = "image-output")]
             "image" => Ok(Self::Image),
+            #[cfg(feature = "ansi")]
+            "sixel" => Ok(Self::Sixel),
+            #[cfg(feature = "ansi")]
+            "kitty" => Ok(Self::Kitty),
+            #[cfg(feature = "ansi")]
+            "iterm" => Ok(Self::Iterm),
+            "svg" => Ok(Self::Svg),
+            "pdf" => Ok(Self::Pdf),
 
-            #[cfg(target_os = "linux")]
+            #[cfg(all(target_os = "linux", feature = "framebuffer"))]
             "framebuffer" => Ok(Self::Framebuffer),
 
             _ => Err(()),
@@ -52,7 +123,85 @@ impl FromStr for Renderers {
 
 /// get name of default renderer
 pub fn default_renderer_name() -> String {
-    "ansi".to_string()
+    if cfg!(feature = "ansi") {
+        "auto"
+    } else if cfg!(feature = "image-output") {
+        "image"
+    } else {
+        "svg"
+    }.to_string()
+}
+
+/// pick the best renderer for the terminal we're running in, filling in any options it needs that weren't already given.
+/// the names of any given options it doesn't have are given back too, since they're left out
+#[cfg(feature = "ansi")]
+pub fn detect_renderer(options: &str) -> (Renderers, String, Vec<String>) {
+    let capabilities = detect();
+
+    let name = if capabilities.kitty {
+        Renderers::Kitty
+    } else if capabilities.iterm {
+        Renderers::Iterm
+    } else if capabilities.sixel {
+        Renderers::Sixel
+    } else if let Some(name) = framebuffer_renderer(&capabilities, options) {
+        name
+    } else {
+        Renderers::Ansi
+    };
+
+    let mut mapping = match serde_yaml::from_str::<serde_yaml::Mapping>(options) {
+        Ok(mapping) => mapping,
+        // the renderer will complain about the options when it's created
+        Err(_) => return (name, options.to_string(), Vec::new()),
+    };
+
+    // options meant for a different renderer than the one that was picked are dropped, since they'd either stop it
+    // from being created or be ignored anyway
+    let known = default_options(&name);
+    let mut ignored = Vec::new();
+
+    mapping = mapping.into_iter()
+        .filter(|(key, _)| {
+            let is_known = known.get(key).is_some();
+
+            if !is_known {
+                ignored.push(key.as_str().map_or_else(|| format!("{:?}", key), str::to_string));
+            }
+
+            is_known
+        })
+        .collect();
+
+    // tell the ANSI renderer how many colors it can use, unless it's been told already
+    if let Renderers::Ansi = name {
+        let key = serde_yaml::Value::from("color_depth");
+
+        if !mapping.contains_key(&key) {
+            mapping.insert(key, serde_yaml::to_value(capabilities.color_depth).unwrap());
+        }
+    }
+
+    let options = serde_yaml::to_string(&mapping).unwrap();
+
+    (name, options, ignored)
+}
+
+/// the framebuffer renderer if it can be used instead of the terminal, which is only when we're in the linux console
+/// (rather than a graphical session that owns the screen) and the framebuffer device can be opened
+#[cfg(all(feature = "ansi", target_os = "linux", feature = "framebuffer"))]
+fn framebuffer_renderer(capabilities: &Capabilities, options: &str) -> Option<Renderers> {
+    let graphical = std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let device = serde_yaml::from_str::<FramebufferRendererOptions>(options).unwrap_or_default().device;
+
+    let usable = capabilities.linux_console && !graphical && std::fs::OpenOptions::new().read(true).write(true).open(device).is_ok();
+    usable.then_some(Renderers::Framebuffer)
+}
+
+/// the framebuffer renderer isn't available, so the terminal is always used
+#[cfg(all(feature = "ansi", not(all(target_os = "linux", feature = "framebuffer"))))]
+fn framebuffer_renderer(_capabilities: &Capabilities, _options: &str) -> Option<Renderers> {
+    None
 }
 
 /// list all available renderers
@@ -63,36 +212,80 @@ pub fn list_renderers() {
     }
 }
 
+/// a renderer made by create_renderer, along with how it was picked
+pub struct CreatedRenderer {
+    /// the renderer itself
+    pub renderer: AnyRenderer,
+
+    /// which renderer it is- this is only different to the one asked for if that was `Auto`, in which case one was
+    /// picked for the terminal
+    pub name: Renderers,
+
+    /// options that were left out because the renderer that was picked doesn't have them
+    pub ignored_options: Vec<String>,
+}
+
 /// create a new renderer given its name and options
-pub fn create_renderer(name: Renderers, options: &str) -> Box<dyn Renderer> {
+pub fn create_renderer(name: Renderers, options: &str) -> Result<CreatedRenderer, PrideError> {
+    let renderer = match name {
+        #[cfg(feature = "ansi")]
+        Renderers::Auto => {
+            let (name, options, ignored_options) = detect_renderer(options);
+            return Ok(CreatedRenderer { ignored_options, ..create_renderer(name, &options)? });
+        },
+        #[cfg(feature = "ansi")]
+        Renderers::Ansi => AnyRenderer::Raster(Box::new(AnsiRenderer::new(options)?)),
+        #[cfg(feature = "image-output")]
+        Renderers::Image => AnyRenderer::Raster(Box::new(ImageRenderer::new(options)?)),
+        #[cfg(feature = "ansi")]
+        Renderers::Sixel => AnyRenderer::Raster(Box::new(SixelRenderer::new(options)?)),
+        #[cfg(feature = "ansi")]
+        Renderers::Kitty => AnyRenderer::Raster(Box::new(KittyRenderer::new(options)?)),
+        #[cfg(feature = "ansi")]
+        Renderers::Iterm => AnyRenderer::Raster(Box::new(ItermRenderer::new(options)?)),
+        Renderers::Svg => AnyRenderer::Vector(Box::new(SvgRenderer::new(options)?)),
+        Renderers::Pdf => AnyRenderer::Vector(Box::new(PdfRenderer::new(options)?)),
+
+        #[cfg(all(target_os = "linux", feature = "framebuffer"))]
+        Renderers::Framebuffer => AnyRenderer::Raster(Box::new(FramebufferRenderer::new(options)?)),
+    };
+
+    Ok(CreatedRenderer { renderer, name, ignored_options: Vec::new() })
+}
+
+/// get the default options of a renderer
+fn default_options(name: &Renderers) -> serde_yaml::Value {
     match name {
-        Renderers::Ansi => Box::new(AnsiRenderer::new(options)),
-        Renderers::Image => Box::new(ImageRenderer::new(options)),
+        // picking a renderer doesn't take any options of its own
+        #[cfg(feature = "ansi")]
+        Renderers::Auto => serde_yaml::Value::Mapping(Default::default()),
+        #[cfg(feature = "ansi")]
+        Renderers::Ansi => serde_yaml::to_value(AnsiRendererOptions::default()).unwrap(),
+        #[cfg(feature = "image-output")]
+        Renderers::Image => serde_yaml::to_value(ImageRendererOptions::default()).unwrap(),
+        #[cfg(feature = "ansi")]
+        Renderers::Sixel => serde_yaml::to_value(SixelRendererOptions::default()).unwrap(),
+        #[cfg(feature = "ansi")]
+        Renderers::Kitty => serde_yaml::to_value(KittyRendererOptions::default()).unwrap(),
+        #[cfg(feature = "ansi")]
+        Renderers::Iterm => serde_yaml::to_value(ItermRendererOptions::default()).unwrap(),
+        Renderers::Svg => serde_yaml::to_value(SvgRendererOptions::default()).unwrap(),
+        Renderers::Pdf => serde_yaml::to_value(PdfRendererOptions::default()).unwrap(),
 
-        #[cfg(target_os = "linux")]
-        Renderers::Framebuffer => Box::new(FramebufferRenderer::new(options)),
+        #[cfg(all(target_os = "linux", feature = "framebuffer"))]
+        Renderers::Framebuffer => serde_yaml::to_value(FramebufferRendererOptions::default()).unwrap(),
     }
 }
 
 /// list options of renderer
 pub fn list_options(name: Renderers) {
-    let options = match name {
-        Renderers::Ansi => {
-            let options: AnsiRendererOptions = Default::default();
-            serde_yaml::to_string(&options).unwrap()
-        },
-        Renderers::Image => {
-            let options: ImageRendererOptions = Default::default();
-            serde_yaml::to_string(&options).unwrap()
-        },
-
-        #[cfg(target_os = "linux")]
-        Renderers::Framebuffer => {
-            let options: FramebufferRendererOptions = Default::default();
-            serde_yaml::to_string(&options).unwrap()
-        },
-    };
+    // list the options of whichever renderer would be picked
+    #[cfg(feature = "ansi")]
+    if let Renderers::Auto = name {
+        let (name, ..) = detect_renderer("{}");
+        return list_options(name);
+    }
 
     println!("default options for renderer {:?}:", name);
-    println!("{}", options);
+    println!("{}", serde_yaml::to_string(&default_options(&name)).unwrap());
 }
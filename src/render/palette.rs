@@ -0,0 +1,99 @@
+//! terminal color palettes and perceptual color matching
+
+use crate::bitmap::Color;
+use lazy_static::lazy_static;
+
+/// a color in the CIELAB color space
+#[derive(Debug, Copy, Clone)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Lab {
+    /// squared CIE76 color difference (delta E) between two colors
+    pub fn distance(&self, other: &Lab) -> f64 {
+        let l = self.l - other.l;
+        let a = self.a - other.a;
+        let b = self.b - other.b;
+        l * l + a * a + b * b
+    }
+}
+
+impl From<Color> for Lab {
+    fn from(color: Color) -> Self {
+        // undo sRGB gamma
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+        };
+
+        let red = linear(color.red);
+        let green = linear(color.green);
+        let blue = linear(color.blue);
+
+        // convert to XYZ, relative to the D65 white point
+        let x = (0.4124 * red + 0.3576 * green + 0.1805 * blue) / 0.95047;
+        let y = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+        let z = (0.0193 * red + 0.1192 * green + 0.9505 * blue) / 1.08883;
+
+        let f = |t: f64| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+
+        Self {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+lazy_static! {
+    /// the default xterm 256 color palette
+    pub static ref XTERM_PALETTE: Vec<Color> = {
+        // 16 system colors
+        let mut palette = vec![
+            Color::new(0, 0, 0), Color::new(205, 0, 0), Color::new(0, 205, 0), Color::new(205, 205, 0),
+            Color::new(0, 0, 238), Color::new(205, 0, 205), Color::new(0, 205, 205), Color::new(229, 229, 229),
+            Color::new(127, 127, 127), Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(255, 255, 0),
+            Color::new(92, 92, 255), Color::new(255, 0, 255), Color::new(0, 255, 255), Color::new(255, 255, 255),
+        ];
+
+        // 6x6x6 color cube
+        const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        for red in LEVELS {
+            for green in LEVELS {
+                for blue in LEVELS {
+                    palette.push(Color::new(red, green, blue));
+                }
+            }
+        }
+
+        // 24 step grayscale ramp
+        for i in 0..24 {
+            palette.push(Color::new(8 + i * 10, 8 + i * 10, 8 + i * 10));
+        }
+
+        palette
+    };
+
+    /// the xterm palette in CIELAB, so it doesn't have to be converted every time
+    static ref XTERM_PALETTE_LAB: Vec<Lab> = XTERM_PALETTE.iter().map(|c| Lab::from(*c)).collect();
+}
+
+/// finds the index of the color in the first `size` entries of the xterm palette that looks closest to the given color
+pub fn nearest_xterm(color: Color, size: usize) -> u8 {
+    let lab = Lab::from(color);
+
+    XTERM_PALETTE_LAB[..size].iter().enumerate()
+        .min_by(|(_, a), (_, b)| a.distance(&lab).total_cmp(&b.distance(&lab)))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0)
+}
+
+/// whether a color is closer to white than black in lightness
+pub fn is_light(color: Color) -> bool {
+    Lab::from(color).l >= 50.0
+}
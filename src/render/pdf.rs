@@ -0,0 +1,503 @@
+//! renderer that writes flags out as PDF documents
+
+use crate::bitmap::{Color, FillRule, ImageFilter};
+use crate::error::PrideError;
+use crate::shape::{Drawing, Fill, Gradient, GradientKind, Shape};
+use image::RgbaImage;
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use serde::{Serialize, Deserialize};
+use std::{
+    fs,
+    io::{Write, stdout},
+    path::PathBuf,
+    sync::Arc,
+};
+use super::VectorRenderer;
+
+/// font size of the caption, in points
+const CAPTION_SIZE: f64 = 18.0;
+
+/// widths of the printable ASCII characters in Helvetica, in thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// size of the page the flag is drawn on
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    /// page is sized to fit the flag
+    Fit,
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    /// size of the page in portrait orientation, in points
+    fn dimensions(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Fit => None,
+            Self::A3 => Some((841.89, 1190.55)),
+            Self::A4 => Some((595.28, 841.89)),
+            Self::A5 => Some((419.53, 595.28)),
+            Self::Letter => Some((612.0, 792.0)),
+            Self::Legal => Some((612.0, 1008.0)),
+        }
+    }
+}
+
+/// options for PDF renderer
+#[derive(Serialize, Deserialize)]
+pub struct PdfRendererOptions {
+    /// file to write the document to- written to stdout if left empty
+    #[serde(default)]
+    pub output: PathBuf,
+
+    /// size of the page. fixed sizes are turned sideways for flags that are wider than they are tall
+    #[serde(default = "default_page")]
+    pub page: PageSize,
+
+    /// width of the flag in points, only used when the page is sized to fit the flag
+    #[serde(default = "default_width")]
+    pub width: f64,
+
+    /// empty space around the edges of the page, in points
+    #[serde(default = "default_margin")]
+    pub margin: f64,
+
+    /// whether to write the name of the flag underneath it
+    #[serde(default)]
+    pub caption: bool,
+}
+
+fn default_page() -> PageSize { PageSize::Fit }
+fn default_width() -> f64 { 720.0 }
+fn default_margin() -> f64 { 36.0 }
+
+impl Default for PdfRendererOptions {
+    fn default() -> Self {
+        Self {
+            output: PathBuf::from(""),
+            page: default_page(),
+            width: default_width(),
+            margin: default_margin(),
+            caption: false,
+        }
+    }
+}
+
+/// renderer that renders to a PDF file
+pub struct PdfRenderer {
+    pub options: PdfRendererOptions,
+}
+
+impl PdfRenderer {
+    /// create a new PdfRenderer with the given options
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
+    }
+
+    /// height of the space reserved for the caption
+    fn caption_height(&self) -> f64 {
+        if self.options.caption { CAPTION_SIZE * 2.0 } else { 0.0 }
+    }
+
+    /// gets the size of the page for a drawing of the given size
+    fn page_size(&self, width: f64, height: f64) -> (f64, f64) {
+        match self.options.page.dimensions() {
+            Some((page_width, page_height)) if width > height => (page_height, page_width),
+            Some(size) => size,
+            None => (width + self.options.margin * 2.0, height + self.options.margin * 2.0 + self.caption_height()),
+        }
+    }
+}
+
+impl VectorRenderer for PdfRenderer {
+    fn render(&mut self, drawing: &Drawing) -> Result<(), PrideError> {
+        let (page_width, page_height) = self.page_size(drawing.width, drawing.height);
+
+        // the drawing and its caption are centered on the page. PDF coordinates start at the bottom left of the page,
+        // so everything has to be flipped
+        let left = (page_width - drawing.width) / 2.0;
+        let top = page_height - (page_height - drawing.height - self.caption_height()) / 2.0;
+
+        let mut content = Vec::new();
+
+        // everything the page uses that needs its own objects
+        let mut resources = Resources::default();
+
+        for shape in drawing.shapes.iter() {
+            match shape {
+                Shape::Rect { x, y, width, height, fill } => {
+                    let path = format!("{} {} {} {} re ", num(left + x), num(top - y - height), num(*width), num(*height));
+                    paint(&mut content, &mut resources, &path, false, fill, (left, top));
+                },
+                Shape::Polygon { points, fill_rule, fill } => {
+                    let mut path = String::new();
+                    for (i, (x, y)) in points.iter().enumerate() {
+                        path.push_str(&format!("{} {} {} ", num(left + x), num(top - y), if i == 0 { "m" } else { "l" }));
+                    }
+                    path.push_str("h ");
+
+                    paint(&mut content, &mut resources, &path, *fill_rule == FillRule::EvenOdd, fill, (left, top));
+                },
+                Shape::Ellipse { cx, cy, rx, ry, fill } => {
+                    let path = ellipse_path(left + cx, top - cy, *rx, *ry);
+                    paint(&mut content, &mut resources, &path, false, fill, (left, top));
+                },
+                Shape::Ring { cx, cy, rx, ry, width, fill } => {
+                    // the band between the two ellipses is filled with the even-odd rule, cutting the inner one out
+                    let mut path = ellipse_path(left + cx, top - cy, rx + width / 2.0, ry + width / 2.0);
+                    if rx - width / 2.0 > 0.0 && ry - width / 2.0 > 0.0 {
+                        path.push_str(&ellipse_path(left + cx, top - cy, rx - width / 2.0, ry - width / 2.0));
+                    }
+
+                    paint(&mut content, &mut resources, &path, true, fill, (left, top));
+                },
+                Shape::Image { x, y, width, height, image, filter } => {
+                    // images are drawn into a unit square, so it has to be stretched out to the right size
+                    writeln!(
+                        content, "q {} 0 0 {} {} {} cm /Im{} Do Q",
+                        num(*width), num(*height), num(left + x), num(top - y - height), resources.images.len(),
+                    ).unwrap();
+                    resources.images.push((image, *filter));
+                },
+            }
+        }
+
+        // center caption underneath the drawing
+        if let (true, Some(name)) = (self.options.caption, &drawing.name) {
+            let text = encode_text(name);
+            let text_width = name.chars().map(char_width).sum::<f64>() * CAPTION_SIZE;
+            let x = left + drawing.width / 2.0 - text_width / 2.0;
+            let y = top - drawing.height - CAPTION_SIZE * 1.5;
+
+            write!(content, "0 g BT /F1 {} Tf {} {} Td (", num(CAPTION_SIZE), num(x), num(y)).unwrap();
+            content.extend_from_slice(&text);
+            writeln!(content, ") Tj ET").unwrap();
+        }
+
+        let mut document = Document::new();
+        document.add(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        document.add(b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec());
+        let page = document.add(Vec::new()); // filled in once everything it uses has been added
+        document.add_stream("", &content);
+        document.add(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec());
+
+        let mut shading_resources = String::new();
+        for (i, shading) in resources.shadings.iter().enumerate() {
+            shading_resources.push_str(&format!("/Sh{} {} 0 R ", i, shading.add_to(&mut document)));
+        }
+
+        let mut state_resources = String::new();
+        for (i, state) in resources.states.iter().enumerate() {
+            let state = match state {
+                GraphicsState::Alpha(alpha) => document.add(format!("<< /ca {} >>", num(*alpha)).into_bytes()),
+                GraphicsState::Mask(mask) => {
+                    // the mask is a form that draws the shading, with its brightness used as the alpha of whatever's
+                    // drawn afterwards. the form is as big as possible, so the shading always covers what's drawn
+                    let shading = mask.add_to(&mut document);
+                    let form = document.add_stream(
+                        &format!(
+                            "/Type /XObject /Subtype /Form /BBox [-100000 -100000 100000 100000] \
+                             /Group << /S /Transparency /CS /DeviceGray >> /Resources << /Shading << /Sh0 {} 0 R >> >> ",
+                            shading,
+                        ),
+                        b"/Sh0 sh",
+                    );
+                    document.add(format!("<< /SMask << /S /Luminosity /G {} 0 R >> >>", form).into_bytes())
+                },
+            };
+            state_resources.push_str(&format!("/GS{} {} 0 R ", i, state));
+        }
+
+        let mut image_resources = String::new();
+        for (i, (image, filter)) in resources.images.iter().enumerate() {
+            let (colors, alpha): (Vec<_>, Vec<_>) = image.pixels()
+                .map(|pixel| ([pixel[0], pixel[1], pixel[2]], pixel[3]))
+                .unzip();
+            let size = format!("/Type /XObject /Subtype /Image /Width {} /Height {} /BitsPerComponent 8 ", image.width(), image.height());
+            let interpolate = if *filter == ImageFilter::Nearest { "" } else { "/Interpolate true " };
+
+            // the alpha channel goes in its own grayscale image, which is used as a soft mask
+            let mask = document.add_stream(
+                &format!("{}/ColorSpace /DeviceGray {}/Filter /FlateDecode ", size, interpolate),
+                &compress_to_vec_zlib(&alpha, 6),
+            );
+            let image = document.add_stream(
+                &format!("{}/ColorSpace /DeviceRGB {}/SMask {} 0 R /Filter /FlateDecode ", size, interpolate, mask),
+                &compress_to_vec_zlib(&colors.concat(), 6),
+            );
+            image_resources.push_str(&format!("/Im{} {} 0 R ", i, image));
+        }
+
+        document.set(page, format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 5 0 R >> /Shading << {}>> /ExtGState << {}>> /XObject << {}>> >> /Contents 4 0 R >>",
+            num(page_width), num(page_height), shading_resources, state_resources, image_resources,
+        ).into_bytes());
+
+        let pdf = document.finish();
+
+        // save document
+        if self.options.output.as_os_str().is_empty() {
+            let mut stdout = stdout();
+            stdout.write_all(&pdf)?;
+            stdout.flush()?;
+        } else {
+            fs::write(&self.options.output, pdf)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_size(&self, aspect: f64) -> (f64, f64) {
+        match self.options.page.dimensions() {
+            Some((page_width, page_height)) => {
+                let (page_width, page_height) = if aspect > 1.0 { (page_height, page_width) } else { (page_width, page_height) };
+
+                // the drawing is shrunk to the shape of the flag, so the caption ends up right underneath it
+                let width = (page_width - self.options.margin * 2.0).max(1.0);
+                let height = (page_height - self.options.margin * 2.0 - self.caption_height()).max(1.0);
+
+                if width / height > aspect {
+                    (height * aspect, height)
+                } else {
+                    (width, width / aspect)
+                }
+            },
+            None => (self.options.width, self.options.width / aspect),
+        }
+    }
+}
+
+/// bare-bones PDF document, made up of numbered objects
+struct Document {
+    objects: Vec<Vec<u8>>,
+}
+
+impl Document {
+    fn new() -> Self {
+        Self { objects: Vec::new() }
+    }
+
+    /// adds an object to the document, returning its number
+    fn add(&mut self, object: Vec<u8>) -> usize {
+        self.objects.push(object);
+        self.objects.len()
+    }
+
+    /// replaces the object with the given number
+    fn set(&mut self, number: usize, object: Vec<u8>) {
+        self.objects[number - 1] = object;
+    }
+
+    /// adds a stream object to the document with the given extra dictionary entries, returning its number
+    fn add_stream(&mut self, dictionary: &str, data: &[u8]) -> usize {
+        let mut object = format!("<< {}/Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+        object.extend_from_slice(data);
+        object.extend_from_slice(b"\nendstream");
+        self.add(object)
+    }
+
+    /// writes out the whole document, with the first object as its root
+    fn finish(self) -> Vec<u8> {
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+
+        for (i, object) in self.objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            writeln!(pdf, "{} 0 obj", i + 1).unwrap();
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+
+        // cross-reference table, so readers can find objects
+        let xref = pdf.len();
+        write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).unwrap();
+        for offset in offsets.iter() {
+            writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+        }
+
+        write!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", offsets.len() + 1, xref).unwrap();
+
+        pdf
+    }
+}
+
+/// everything used by the page that needs its own objects
+#[derive(Default)]
+struct Resources<'a> {
+    /// gradients
+    shadings: Vec<Shading>,
+
+    /// transparency settings
+    states: Vec<GraphicsState>,
+
+    /// images and how to scale them
+    images: Vec<(&'a Arc<RgbaImage>, ImageFilter)>,
+}
+
+/// gradient drawn with a shading
+struct Shading {
+    /// start of the shading dictionary, giving its type and position
+    geometry: String,
+
+    /// colors along the gradient
+    samples: Vec<u8>,
+
+    /// whether the colors are grayscale rather than RGB
+    gray: bool,
+}
+
+impl Shading {
+    /// adds the shading to a document along with the function giving its colors, returning its number
+    fn add_to(&self, document: &mut Document) -> usize {
+        let (color_space, range, components) = if self.gray {
+            ("/DeviceGray", "[0 1]", 1)
+        } else {
+            ("/DeviceRGB", "[0 1 0 1 0 1]", 3)
+        };
+
+        let function = document.add_stream(
+            &format!("/FunctionType 0 /Domain [0 1] /Range {} /Size [{}] /BitsPerSample 8 ", range, self.samples.len() / components),
+            &self.samples,
+        );
+
+        document.add(format!(
+            "<< {}/ColorSpace {} /Function {} 0 R /Extend [true true] >>",
+            self.geometry, color_space, function,
+        ).into_bytes())
+    }
+}
+
+/// how see-through things are drawn
+enum GraphicsState {
+    /// everything is equally see-through
+    Alpha(f64),
+
+    /// the alpha comes from the brightness of a grayscale gradient
+    Mask(Shading),
+}
+
+/// fills in a path, which is filled with the even-odd rule if `even_odd` is set. `origin` is where the top left
+/// of the drawing is on the page
+fn paint(content: &mut Vec<u8>, resources: &mut Resources<'_>, path: &str, even_odd: bool, fill: &Fill, (left, top): (f64, f64)) {
+    let gradient = match fill {
+        Fill::Solid(color) if color.alpha == 255 => {
+            writeln!(content, "{} rg {}{}", pdf_color(*color), path, if even_odd { "f*" } else { "f" }).unwrap();
+            return;
+        },
+        Fill::Solid(color) if color.alpha == 0 => return,
+        Fill::Solid(color) => {
+            writeln!(
+                content, "q /GS{} gs {} rg {}{} Q",
+                resources.states.len(), pdf_color(*color), path, if even_odd { "f*" } else { "f" },
+            ).unwrap();
+            resources.states.push(GraphicsState::Alpha(color.alpha as f64 / 255.0));
+            return;
+        },
+        Fill::Gradient(gradient) => gradient,
+    };
+
+    // the path is used to clip the shading, which would otherwise cover the whole page
+    write!(content, "q {}{} n ", path, if even_odd { "W*" } else { "W" }).unwrap();
+
+    let geometry = match gradient.kind {
+        GradientKind::Linear { start, end } => format!(
+            "/ShadingType 2 /Coords [{} {} {} {}] ",
+            num(left + start.0), num(top - start.1), num(left + end.0), num(top - end.1),
+        ),
+        GradientKind::Radial { cx, cy, rx, ry } => {
+            // radial shadings are circles, so stretch a unit circle out to the right size
+            write!(content, "{} 0 0 {} {} {} cm ", num(rx.max(0.001)), num(ry.max(0.001)), num(left + cx), num(top - cy)).unwrap();
+            "/ShadingType 3 /Coords [0 0 0 0 0 1] ".to_string()
+        },
+    };
+
+    let (colors, alpha) = sample_gradient(gradient);
+
+    // see-through gradients are masked by another gradient made from their alpha
+    if gradient.stops.iter().any(|(_, color)| color.alpha < 255) {
+        write!(content, "/GS{} gs ", resources.states.len()).unwrap();
+        resources.states.push(GraphicsState::Mask(Shading { geometry: geometry.clone(), samples: alpha, gray: true }));
+    }
+
+    writeln!(content, "/Sh{} sh Q", resources.shadings.len()).unwrap();
+    resources.shadings.push(Shading { geometry, samples: colors, gray: false });
+}
+
+/// samples the colors and alpha along a gradient, so that they're blended the same way in any color space
+fn sample_gradient(gradient: &Gradient) -> (Vec<u8>, Vec<u8>) {
+    /// how many colors are sampled along the gradient
+    const SAMPLES: usize = 256;
+
+    let colors: Vec<Color> = (0..SAMPLES)
+        .map(|i| gradient.color_at_offset(i as f64 / (SAMPLES - 1) as f64))
+        .collect();
+
+    (
+        colors.iter().flat_map(|color| [color.red, color.green, color.blue]).collect(),
+        colors.iter().map(|color| color.alpha).collect(),
+    )
+}
+
+/// builds a path for an ellipse out of four bezier curves
+fn ellipse_path(cx: f64, cy: f64, rx: f64, ry: f64) -> String {
+    // distance of the control points from the ends of each curve, as a fraction of the radius
+    const KAPPA: f64 = 0.5522847498;
+
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+    format!(
+        "{} {} m {} {} {} {} {} {} c {} {} {} {} {} {} c {} {} {} {} {} {} c {} {} {} {} {} {} c h ",
+        num(cx + rx), num(cy),
+        num(cx + rx), num(cy + ky), num(cx + kx), num(cy + ry), num(cx), num(cy + ry),
+        num(cx - kx), num(cy + ry), num(cx - rx), num(cy + ky), num(cx - rx), num(cy),
+        num(cx - rx), num(cy - ky), num(cx - kx), num(cy - ry), num(cx), num(cy - ry),
+        num(cx + kx), num(cy - ry), num(cx + rx), num(cy - ky), num(cx + rx), num(cy),
+    )
+}
+
+/// formats a color as PDF color components
+fn pdf_color(color: Color) -> String {
+    format!("{} {} {}", num(color.red as f64 / 255.0), num(color.green as f64 / 255.0), num(color.blue as f64 / 255.0))
+}
+
+/// formats a number for use in a PDF file. PDF doesn't allow exponents, so numbers are always written out in full
+fn num(n: f64) -> String {
+    let n = (n * 1000.0).round() / 1000.0;
+    format!("{}", if n == 0.0 { 0.0 } else { n })
+}
+
+/// converts text into an escaped string in the WinAnsi encoding. characters it can't represent are replaced with question marks
+fn encode_text(text: &str) -> Vec<u8> {
+    let mut encoded = Vec::new();
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => encoded.extend_from_slice(&[b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => encoded.push(c as u8),
+            _ => encoded.push(b'?'),
+        }
+    }
+
+    encoded
+}
+
+/// gets the width of a character in Helvetica, relative to the font size
+fn char_width(c: char) -> f64 {
+    match c {
+        ' '..='~' => HELVETICA_WIDTHS[(c as u8 - b' ') as usize] as f64 / 1000.0,
+        _ => 0.556,
+    }
+}
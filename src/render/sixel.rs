@@ -0,0 +1,252 @@
+//! renderer that draws to the terminal with DEC sixel graphics
+
+use crate::bitmap::{Bitmap, Color};
+use crate::error::PrideError;
+use serde::{Serialize, Deserialize};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+};
+use super::{
+    Renderer,
+    terminal::{cell_size, display, pixel_size},
+};
+use termion::{cursor, clear};
+
+/// options for sixel renderer
+#[derive(Serialize, Deserialize)]
+pub struct SixelRendererOptions {
+    /// maximum number of colors in the palette (most terminals support up to 256)
+    #[serde(default = "default_colors")]
+    pub colors: usize,
+}
+
+fn default_colors() -> usize { 256 }
+
+impl Default for SixelRendererOptions {
+    fn default() -> Self {
+        Self {
+            colors: default_colors(),
+        }
+    }
+}
+
+/// renderer that renders to the terminal with sixel graphics
+pub struct SixelRenderer {
+    pub options: SixelRendererOptions,
+}
+
+impl SixelRenderer {
+    /// create a new SixelRenderer with the given options
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        let options: SixelRendererOptions = serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?;
+
+        if !(2..=256).contains(&options.colors) {
+            return Err(PrideError::InvalidRendererOption("colors must be between 2 and 256".to_string()));
+        }
+
+        Ok(Self { options })
+    }
+}
+
+impl Renderer for SixelRenderer {
+    /// draws a bitmap to the terminal as a sixel image
+    fn render(&mut self, bitmap: &Bitmap) -> Result<(), PrideError> {
+        let palette = quantize(bitmap, self.options.colors);
+
+        // maps every color in the bitmap to its closest palette entry
+        let mut lookup: HashMap<Color, usize> = HashMap::new();
+        let indices: Vec<Vec<usize>> = bitmap.data.iter().map(|row| {
+            row.iter().map(|color| *lookup.entry(*color).or_insert_with(|| nearest(&palette, *color))).collect()
+        }).collect();
+
+        let mut sequence = String::new();
+
+        // clear screen
+        sequence.push_str(cursor::Hide.as_ref());
+        sequence.push_str(clear::All.as_ref());
+        sequence.push_str(&cursor::Goto(1, 1).to_string());
+
+        // start sixel sequence, with square pixels and the image size
+        sequence.push_str("\x1bP0;1;0q");
+        write!(sequence, "\"1;1;{};{}", bitmap.width, bitmap.height).unwrap();
+
+        // define palette- sixel color components are percentages
+        for (i, color) in palette.iter().enumerate() {
+            write!(
+                sequence, "#{};2;{};{};{}", i,
+                color.red as usize * 100 / 255,
+                color.green as usize * 100 / 255,
+                color.blue as usize * 100 / 255,
+            ).unwrap();
+        }
+
+        // the image is drawn in bands of 6 rows, one pass per color
+        for band in (0..bitmap.height).step_by(6) {
+            let rows = &indices[band..(band + 6).min(bitmap.height)];
+
+            // find out which colors are used in this band
+            let mut used = vec![false; palette.len()];
+            for row in rows.iter() {
+                for index in row.iter() {
+                    used[*index] = true;
+                }
+            }
+
+            let mut first = true;
+
+            for (index, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+                // return to the start of the band before drawing another color
+                if !first {
+                    sequence.push('$');
+                }
+                first = false;
+
+                write!(sequence, "#{}", index).unwrap();
+
+                // build sixel characters for this color, compressing runs of the same character
+                let mut last: Option<char> = None;
+                let mut count = 0;
+
+                for x in 0..bitmap.width {
+                    let mut bits = 0;
+                    for (bit, row) in rows.iter().enumerate() {
+                        if row[x] == index {
+                            bits |= 1 << bit;
+                        }
+                    }
+
+                    let c = char::from(63 + bits);
+
+                    if last == Some(c) {
+                        count += 1;
+                    } else {
+                        if let Some(last) = last {
+                            push_run(&mut sequence, last, count);
+                        }
+                        last = Some(c);
+                        count = 1;
+                    }
+                }
+
+                if let Some(last) = last {
+                    push_run(&mut sequence, last, count);
+                }
+            }
+
+            // move on to the next band
+            sequence.push('-');
+        }
+
+        // end sixel sequence
+        sequence.push_str("\x1b\\");
+
+        Ok(display(&sequence)?)
+    }
+
+    /// gets max size of renderer
+    fn get_size(&self) -> Result<(usize, usize), PrideError> {
+        let (width, height) = pixel_size()?;
+        let (_, cell_height) = cell_size()?;
+
+        // leave the last line free so the terminal doesn't scroll once the image is drawn
+        Ok((width, height.saturating_sub(cell_height).max(1)))
+    }
+}
+
+/// adds a run of identical sixel characters to the sequence
+fn push_run(sequence: &mut String, c: char, count: usize) {
+    if count > 3 {
+        write!(sequence, "!{}{}", count, c).unwrap();
+    } else {
+        for _ in 0..count {
+            sequence.push(c);
+        }
+    }
+}
+
+/// finds the index of the palette entry closest to the given color
+fn nearest(palette: &[Color], color: Color) -> usize {
+    let distance = |other: &Color| {
+        let red = other.red as i32 - color.red as i32;
+        let green = other.green as i32 - color.green as i32;
+        let blue = other.blue as i32 - color.blue as i32;
+        red * red + green * green + blue * blue
+    };
+
+    palette.iter().enumerate().min_by_key(|(_, other)| distance(other)).map(|(i, _)| i).unwrap_or(0)
+}
+
+/// reduces the colors of a bitmap to a palette of at most `max` colors with the median cut algorithm
+fn quantize(bitmap: &Bitmap, max: usize) -> Vec<Color> {
+    // count how often each color is used
+    let mut counts: HashMap<Color, usize> = HashMap::new();
+    for row in bitmap.data.iter() {
+        for color in row.iter() {
+            *counts.entry(*color).or_insert(0) += 1;
+        }
+    }
+
+    let colors: Vec<(Color, usize)> = counts.into_iter().collect();
+
+    // no need to do anything if all the colors already fit
+    if colors.len() <= max {
+        return colors.into_iter().map(|(color, _)| color).collect();
+    }
+
+    let channel = |color: &Color, i: usize| match i {
+        0 => color.red,
+        1 => color.green,
+        _ => color.blue,
+    };
+
+    // finds the channel with the widest range in a box and how wide that range is
+    let widest = |colors: &[(Color, usize)]| {
+        (0..3).map(|i| {
+            let min = colors.iter().map(|(color, _)| channel(color, i)).min().unwrap();
+            let max = colors.iter().map(|(color, _)| channel(color, i)).max().unwrap();
+            (i, max - min)
+        }).max_by_key(|(_, range)| *range).unwrap()
+    };
+
+    let mut boxes = vec![colors];
+
+    while boxes.len() < max {
+        // split the box with the widest range of colors
+        let (index, (channel_index, range)) = boxes.iter().enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest(colors)))
+            .max_by_key(|(_, (_, range))| *range)
+            .unwrap_or((0, (0, 0)));
+
+        if range == 0 {
+            break;
+        }
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(color, _)| channel(color, channel_index));
+
+        // split at the weighted median so both halves cover roughly the same amount of pixels
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let mut split = 1;
+        for (i, (_, count)) in colors.iter().enumerate() {
+            seen += count;
+            if seen * 2 >= total {
+                split = (i + 1).clamp(1, colors.len() - 1);
+                break;
+            }
+        }
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    // each box becomes the weighted average of its colors
+    boxes.iter().map(|colors| {
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let average = |i: usize| (colors.iter().map(|(color, count)| channel(color, i) as usize * count).sum::<usize>() / total) as u8;
+        Color::new(average(0), average(1), average(2))
+    }).collect()
+}
@@ -0,0 +1,217 @@
+//! renderer that writes flags out as SVG images
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use crate::bitmap::{Color, ColorSpace, FillRule, ImageFilter};
+use crate::error::PrideError;
+use crate::shape::{Drawing, Fill, GradientKind, Shape};
+use image::ImageOutputFormat;
+use serde::{Serialize, Deserialize};
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{Cursor, Write, stdout},
+    path::PathBuf,
+};
+use super::VectorRenderer;
+
+/// options for SVG renderer
+#[derive(Serialize, Deserialize)]
+pub struct SvgRendererOptions {
+    /// file to write the image to- written to stdout if left empty
+    #[serde(default)]
+    pub output: PathBuf,
+
+    /// width of the image
+    #[serde(default = "default_width")]
+    pub width: f64,
+
+    /// height of the image- picked from the flag's aspect ratio if not set
+    #[serde(default)]
+    pub height: Option<f64>,
+}
+
+fn default_width() -> f64 { 640.0 }
+
+impl Default for SvgRendererOptions {
+    fn default() -> Self {
+        Self {
+            output: PathBuf::from(""),
+            width: default_width(),
+            height: None,
+        }
+    }
+}
+
+/// renderer that renders to an SVG file
+pub struct SvgRenderer {
+    pub options: SvgRendererOptions,
+}
+
+impl SvgRenderer {
+    /// create a new SvgRenderer with the given options
+    pub fn new(options: &str) -> Result<Self, PrideError> {
+        Ok(Self {
+            options: serde_yaml::from_str(options).map_err(PrideError::RendererOptions)?,
+        })
+    }
+}
+
+impl VectorRenderer for SvgRenderer {
+    fn render(&mut self, drawing: &Drawing) -> Result<(), PrideError> {
+        let mut svg = String::new();
+
+        writeln!(
+            svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            num(drawing.width), num(drawing.height),
+        ).unwrap();
+
+        // gradients are numbered so shapes can refer to them
+        let mut gradients = 0;
+
+        for shape in drawing.shapes.iter() {
+            match shape {
+                Shape::Rect { x, y, width, height, fill } => {
+                    let fill = paint(&mut svg, &mut gradients, fill);
+                    writeln!(
+                        svg, r#"  <rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                        num(*x), num(*y), num(*width), num(*height), fill,
+                    ).unwrap();
+                },
+                Shape::Polygon { points, fill_rule, fill } => {
+                    let fill = paint(&mut svg, &mut gradients, fill);
+                    writeln!(
+                        svg, r#"  <polygon points="{}" {} fill-rule="{}"/>"#,
+                        points.iter().map(|(x, y)| format!("{},{}", num(*x), num(*y))).collect::<Vec<_>>().join(" "), fill,
+                        match fill_rule {
+                            FillRule::NonZero => "nonzero",
+                            FillRule::EvenOdd => "evenodd",
+                        },
+                    ).unwrap();
+                },
+                Shape::Ellipse { cx, cy, rx, ry, fill } => {
+                    let fill = paint(&mut svg, &mut gradients, fill);
+                    writeln!(
+                        svg, r#"  <ellipse cx="{}" cy="{}" rx="{}" ry="{}" {}/>"#,
+                        num(*cx), num(*cy), num(*rx), num(*ry), fill,
+                    ).unwrap();
+                },
+                Shape::Ring { cx, cy, rx, ry, width, fill } => {
+                    // drawn as two ellipses with the inner one cut out, which matches how rings are rasterized
+                    let mut path = ellipse_path(*cx, *cy, rx + width / 2.0, ry + width / 2.0);
+                    if rx - width / 2.0 > 0.0 && ry - width / 2.0 > 0.0 {
+                        path.push(' ');
+                        path.push_str(&ellipse_path(*cx, *cy, rx - width / 2.0, ry - width / 2.0));
+                    }
+
+                    let fill = paint(&mut svg, &mut gradients, fill);
+                    writeln!(svg, r#"  <path d="{}" {} fill-rule="evenodd"/>"#, path, fill).unwrap();
+                },
+                Shape::Image { x, y, width, height, image, filter } => {
+                    // embedded as a PNG so the file doesn't depend on anything else
+                    let mut png = Cursor::new(Vec::new());
+                    image.write_to(&mut png, ImageOutputFormat::Png)?;
+
+                    writeln!(
+                        svg, r#"  <image x="{}" y="{}" width="{}" height="{}" preserveAspectRatio="none"{} href="data:image/png;base64,{}"/>"#,
+                        num(*x), num(*y), num(*width), num(*height),
+                        if *filter == ImageFilter::Nearest { r#" image-rendering="pixelated""# } else { "" },
+                        STANDARD.encode(png.into_inner()),
+                    ).unwrap();
+                },
+            }
+        }
+
+        svg.push_str("</svg>\n");
+
+        // save image
+        if self.options.output.as_os_str().is_empty() {
+            let mut stdout = stdout();
+            stdout.write_all(svg.as_bytes())?;
+            stdout.flush()?;
+        } else {
+            fs::write(&self.options.output, svg)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_size(&self, aspect: f64) -> (f64, f64) {
+        (self.options.width, self.options.height.unwrap_or(self.options.width / aspect))
+    }
+}
+
+/// gets the fill attributes for the given fill, writing out a definition for it first if it's a gradient
+fn paint(svg: &mut String, gradients: &mut usize, fill: &Fill) -> String {
+    /// how many stops to add between each pair of stops when blending somewhere other than sRGB
+    const STEPS: usize = 8;
+
+    let gradient = match fill {
+        Fill::Solid(color) => return color_attributes(*color, "fill", "fill-opacity"),
+        Fill::Gradient(gradient) => gradient,
+    };
+
+    let id = format!("gradient{}", gradients);
+    *gradients += 1;
+
+    match gradient.kind {
+        GradientKind::Linear { start, end } => writeln!(
+            svg, r#"  <linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+            id, num(start.0), num(start.1), num(end.0), num(end.1),
+        ).unwrap(),
+        GradientKind::Radial { cx, cy, rx, ry } => writeln!(
+            svg, r#"  <radialGradient id="{}" gradientUnits="userSpaceOnUse" cx="0" cy="0" r="1" gradientTransform="translate({} {}) scale({} {})">"#,
+            id, num(cx), num(cy), num(rx), num(ry),
+        ).unwrap(),
+    }
+
+    // SVG viewers only blend in sRGB, so other color spaces are approximated with extra stops in between
+    for (i, (offset, color)) in gradient.stops.iter().enumerate() {
+        if gradient.space != ColorSpace::Srgb && i > 0 {
+            let (from, from_color) = gradient.stops[i - 1];
+
+            for step in 1..STEPS {
+                let t = step as f64 / STEPS as f64;
+                writeln!(
+                    svg, r#"    <stop offset="{}" {}/>"#,
+                    num(from + (offset - from) * t), color_attributes(gradient.space.mix(from_color, *color, t), "stop-color", "stop-opacity"),
+                ).unwrap();
+            }
+        }
+
+        writeln!(svg, r#"    <stop offset="{}" {}/>"#, num(*offset), color_attributes(*color, "stop-color", "stop-opacity")).unwrap();
+    }
+
+    match gradient.kind {
+        GradientKind::Linear { .. } => writeln!(svg, "  </linearGradient>").unwrap(),
+        GradientKind::Radial { .. } => writeln!(svg, "  </radialGradient>").unwrap(),
+    }
+
+    format!(r#"fill="url(#{})""#, id)
+}
+
+/// gets the attributes for a color, with its alpha in a separate opacity attribute since not every viewer
+/// understands colors with alpha
+fn color_attributes(color: Color, name: &str, opacity_name: &str) -> String {
+    let opaque = Color::new(color.red, color.green, color.blue);
+
+    if color.alpha == 255 {
+        format!(r#"{}="{}""#, name, opaque)
+    } else {
+        format!(r#"{}="{}" {}="{}""#, name, opaque, opacity_name, num(color.alpha as f64 / 255.0))
+    }
+}
+
+/// builds path data for an ellipse out of two arcs
+fn ellipse_path(cx: f64, cy: f64, rx: f64, ry: f64) -> String {
+    format!(
+        "M{},{} A{},{} 0 1 0 {},{} A{},{} 0 1 0 {},{} Z",
+        num(cx - rx), num(cy),
+        num(rx), num(ry), num(cx + rx), num(cy),
+        num(rx), num(ry), num(cx - rx), num(cy),
+    )
+}
+
+/// formats a number for use in an SVG file, without needlessly long fractions
+fn num(n: f64) -> String {
+    format!("{}", (n * 1000.0).round() / 1000.0)
+}
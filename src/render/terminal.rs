@@ -0,0 +1,179 @@
+//! helpers shared by the renderers that draw to the terminal
+
+use crate::error::PrideError;
+use std::{
+    io::{self, Read, Write, stdin, stdout},
+    time::{Duration, Instant},
+};
+use termion::{
+    color, cursor, style,
+    event::Event,
+    input::TermRead,
+    raw::IntoRawMode,
+    screen::AlternateScreen,
+};
+
+/// writes a sequence to an alternate screen, then waits for a key to be pressed before switching back
+pub fn display(sequence: &str) -> io::Result<()> {
+    display_with_cleanup(sequence, "")
+}
+
+/// same as `display`, but writes the cleanup sequence before switching back from the alternate screen
+pub fn display_with_cleanup(sequence: &str, cleanup: &str) -> io::Result<()> {
+    // create an alternate terminal buffer to write to so we can have a cleaner switch back
+    let mut stdout = AlternateScreen::from(stdout().into_raw_mode()?); // stdout needs to be set to raw mode to read individual characters
+
+    // put everything on screen, then wait for a key to be pressed before exiting
+    let result = write!(stdout, "{}", sequence).and_then(|_| stdout.flush()).and_then(|_| wait_for_key());
+
+    // undo anything that won't be undone by switching back from the alternate screen, even if something went wrong,
+    // since part of the sequence may have made it out
+    if !cleanup.is_empty() {
+        let _ = write!(stdout, "{}", cleanup);
+        let _ = stdout.flush();
+    }
+
+    reset_terminal(); // terminal is reset just in case we don't support alternate buffers
+
+    result
+}
+
+/// waits for a key to be pressed. keys are read from the terminal itself if stdin isn't it, since the flag might have
+/// been piped in, and there's nothing to wait for if there's no terminal at all
+pub fn wait_for_key() -> io::Result<()> {
+    let keys: Box<dyn Read> = if termion::is_tty(&stdin()) {
+        Box::new(stdin())
+    } else {
+        match termion::get_tty() {
+            Ok(tty) => Box::new(tty),
+            Err(_) => return Ok(()),
+        }
+    };
+
+    for evt in keys.events() {
+        if let Event::Key(_) = evt? { break; } // break out of loop if we get a key event
+    }
+
+    Ok(())
+}
+
+/// sends a query to the terminal and reads its response until `is_complete` is satisfied or the timeout runs out
+pub fn query(request: &str, is_complete: impl Fn(&[u8]) -> bool, timeout: Duration) -> Option<Vec<u8>> {
+    // there's nobody to answer if we're not talking to a terminal
+    if !termion::is_tty(&stdin()) || !termion::is_tty(&stdout()) {
+        return None;
+    }
+
+    // raw mode keeps the response from being echoed back and gives it to us without waiting for a newline
+    let mut stdout = stdout().into_raw_mode().ok()?;
+
+    write!(stdout, "{}", request).ok()?;
+    stdout.flush().ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+
+    while !is_complete(&response) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return discard_input();
+        }
+
+        // wait for input without blocking past the deadline. stdin is read directly so that nothing
+        // past the response ends up stuck in a buffer
+        let mut fd = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+        let mut buf = [0u8; 256];
+
+        let count = unsafe {
+            if libc::poll(&mut fd, 1, remaining.as_millis() as libc::c_int) <= 0 {
+                return discard_input();
+            }
+
+            libc::read(libc::STDIN_FILENO, buf.as_mut_ptr() as *mut libc::c_void, buf.len())
+        };
+
+        if count <= 0 {
+            return None;
+        }
+
+        response.extend_from_slice(&buf[..count as usize]);
+    }
+
+    Some(response)
+}
+
+/// throws away anything waiting to be read from stdin, so that a response that turns up after a query has given up
+/// waiting for it isn't taken as keypresses later on
+fn discard_input() -> Option<Vec<u8>> {
+    unsafe {
+        libc::tcflush(libc::STDIN_FILENO, libc::TCIFLUSH);
+    }
+
+    None
+}
+
+/// gets the size of the terminal window in character cells
+pub fn terminal_size() -> Result<(u16, u16), PrideError> {
+    termion::terminal_size().map_err(PrideError::TerminalSize)
+}
+
+/// gets the size of the terminal window in pixels, guessing from the size in cells if the terminal doesn't report it
+pub fn pixel_size() -> Result<(usize, usize), PrideError> {
+    if let Ok((width, height)) = termion::terminal_size_pixels() {
+        if width > 0 && height > 0 {
+            return Ok((width as usize, height as usize));
+        }
+    }
+
+    // not every terminal fills in the pixel size for ioctl, but most will answer with it when asked with CSI 14 t
+    if let Some(size) = query_pixel_size() {
+        return Ok(size);
+    }
+
+    // most terminal fonts are roughly twice as tall as they are wide
+    let (width, height) = terminal_size()?;
+    Ok((width as usize * 10, height as usize * 20))
+}
+
+/// asks the terminal for the size of its text area in pixels
+fn query_pixel_size() -> Option<(usize, usize)> {
+    // the response looks like CSI 4 ; height ; width t
+    let response = query("\x1b[14t", |response| response.ends_with(b"t"), Duration::from_millis(100))?;
+    let response = String::from_utf8_lossy(&response);
+
+    let mut params = response.trim_start_matches("\x1b[").trim_end_matches('t').split(';');
+    if params.next()? != "4" {
+        return None;
+    }
+
+    let height = params.next()?.parse::<usize>().ok()?;
+    let width = params.next()?.parse::<usize>().ok()?;
+
+    if width > 0 && height > 0 {
+        Some((width, height))
+    } else {
+        None
+    }
+}
+
+/// gets the size of a single character cell in pixels
+pub fn cell_size() -> Result<(usize, usize), PrideError> {
+    let (width, height) = terminal_size()?;
+    let (pixel_width, pixel_height) = pixel_size()?;
+
+    Ok((pixel_width / width.max(1) as usize, pixel_height / height.max(1) as usize))
+}
+
+/// revert any changes we've made while rendering
+pub fn reset_terminal() {
+    let mut stdout = stdout();
+    let sequence = format!("{}{}{}{}", style::Reset, color::Fg(color::Reset), color::Bg(color::Reset), cursor::Show);
+
+    if write!(stdout, "{}", sequence).is_err() { // attempt to recover if an error occurs
+        print!("{}", sequence);
+    }
+
+    if stdout.flush().is_err() {
+        print!("{}", sequence);
+    }
+}
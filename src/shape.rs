@@ -0,0 +1,220 @@
+//! resolution independent shapes, produced by laying out a flag
+
+use crate::bitmap::{Color, ColorSpace, FillRule, ImageFilter, Paint};
+use image::RgbaImage;
+use std::sync::Arc;
+
+/// a single shape, positioned in drawing units
+#[derive(Debug, Clone)]
+pub enum Shape {
+    /// axis-aligned rectangle
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        fill: Fill,
+    },
+
+    /// filled polygon, with its edges connecting each point to the next and the last point back to the first
+    Polygon {
+        points: Vec<(f64, f64)>,
+        fill_rule: FillRule,
+        fill: Fill,
+    },
+
+    /// filled ellipse
+    Ellipse {
+        cx: f64,
+        cy: f64,
+        rx: f64,
+        ry: f64,
+        fill: Fill,
+    },
+
+    /// outline of an ellipse, as the band between two ellipses `width` apart centered on the given radii
+    Ring {
+        cx: f64,
+        cy: f64,
+        rx: f64,
+        ry: f64,
+        width: f64,
+        fill: Fill,
+    },
+
+    /// raster image, stretched to fill a rectangle
+    Image {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        image: Arc<RgbaImage>,
+        filter: ImageFilter,
+    },
+}
+
+/// how a shape is filled in
+#[derive(Debug, Clone)]
+pub enum Fill {
+    /// a single color
+    Solid(Color),
+
+    /// colors blending into each other
+    Gradient(Gradient),
+}
+
+impl Paint for Fill {
+    fn color_at(&self, x: f64, y: f64) -> Color {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient(gradient) => gradient.color_at_offset(gradient.offset_at(x, y)),
+        }
+    }
+}
+
+/// where a gradient is positioned
+#[derive(Debug, Clone)]
+pub enum GradientKind {
+    /// colors change along the line from `start` to `end`, staying the same across it
+    Linear {
+        start: (f64, f64),
+        end: (f64, f64),
+    },
+
+    /// colors change going out from the center to the edge of an ellipse with the given radii
+    Radial {
+        cx: f64,
+        cy: f64,
+        rx: f64,
+        ry: f64,
+    },
+}
+
+/// gradient between colors, positioned in drawing units
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    /// where the gradient is positioned
+    pub kind: GradientKind,
+
+    /// colors along the gradient along with their offsets from 0 to 1, in order
+    pub stops: Vec<(f64, Color)>,
+
+    /// color space the stops are blended in
+    pub space: ColorSpace,
+}
+
+impl Gradient {
+    /// gets how far along the gradient a point is, from 0 at the start to 1 at the end. points past either end
+    /// aren't clamped
+    pub fn offset_at(&self, x: f64, y: f64) -> f64 {
+        match self.kind {
+            GradientKind::Linear { start, end } => {
+                let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+                let length = dx * dx + dy * dy;
+
+                if length > 0.0 { ((x - start.0) * dx + (y - start.1) * dy) / length } else { 0.0 }
+            },
+            GradientKind::Radial { cx, cy, rx, ry } => {
+                if rx > 0.0 && ry > 0.0 { ((x - cx) / rx).hypot((y - cy) / ry) } else { 1.0 }
+            },
+        }
+    }
+
+    /// gets the color at the given offset along the gradient. the first and last colors carry on past the ends
+    pub fn color_at_offset(&self, offset: f64) -> Color {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::new(0, 0, 0),
+        };
+
+        if offset <= first.0 {
+            return first.1;
+        }
+
+        // blend between the stops on either side of the offset
+        for pair in self.stops.windows(2) {
+            let ((from, a), (to, b)) = (pair[0], pair[1]);
+
+            if offset <= to {
+                return if to > from { self.space.mix(a, b, (offset - from) / (to - from)) } else { b };
+            }
+        }
+
+        last.1
+    }
+}
+
+/// a flag that's been laid out as a list of shapes, drawn in order
+#[derive(Debug, Clone)]
+pub struct Drawing {
+    /// width of the drawing
+    pub width: f64,
+
+    /// height of the drawing
+    pub height: f64,
+
+    /// shapes in the drawing, from back to front
+    pub shapes: Vec<Shape>,
+
+    /// name of the flag this drawing was made from, as it should be shown to people
+    pub name: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Color = Color::new(0, 0, 0);
+    const WHITE: Color = Color::new(255, 255, 255);
+    const RED: Color = Color::new(255, 0, 0);
+
+    /// a gradient going left to right across a 100 unit wide drawing
+    fn gradient(stops: Vec<(f64, Color)>) -> Gradient {
+        Gradient { kind: GradientKind::Linear { start: (0.0, 0.0), end: (100.0, 0.0) }, stops, space: ColorSpace::Srgb }
+    }
+
+    #[test]
+    fn ends_carry_on_past_the_first_and_last_stops() {
+        let gradient = gradient(vec![(0.25, BLACK), (0.75, WHITE)]);
+
+        assert_eq!(gradient.color_at_offset(-1.0), BLACK);
+        assert_eq!(gradient.color_at_offset(0.1), BLACK);
+        assert_eq!(gradient.color_at_offset(0.9), WHITE);
+        assert_eq!(gradient.color_at_offset(2.0), WHITE);
+    }
+
+    #[test]
+    fn colors_are_blended_between_neighbouring_stops() {
+        let gradient = gradient(vec![(0.0, BLACK), (0.5, WHITE), (1.0, RED)]);
+
+        assert_eq!(gradient.color_at_offset(0.25), Color::new(128, 128, 128));
+        assert_eq!(gradient.color_at_offset(0.5), WHITE);
+        assert_eq!(gradient.color_at_offset(0.75), Color::new(255, 128, 128));
+    }
+
+    #[test]
+    fn stops_at_the_same_offset_make_a_hard_edge() {
+        let gradient = gradient(vec![(0.0, BLACK), (0.5, BLACK), (0.5, WHITE), (1.0, WHITE)]);
+
+        assert_eq!(gradient.color_at_offset(0.49), BLACK);
+        assert_eq!(gradient.color_at_offset(0.51), WHITE);
+    }
+
+    #[test]
+    fn linear_offsets_follow_the_line() {
+        let gradient = gradient(vec![(0.0, BLACK), (1.0, WHITE)]);
+
+        assert_eq!(gradient.offset_at(0.0, 50.0), 0.0);
+        assert_eq!(gradient.offset_at(25.0, 10.0), 0.25);
+        assert_eq!(gradient.offset_at(150.0, 0.0), 1.5);
+    }
+
+    #[test]
+    fn radial_offsets_follow_the_ellipse() {
+        let gradient = Gradient { kind: GradientKind::Radial { cx: 50.0, cy: 50.0, rx: 50.0, ry: 25.0 }, ..gradient(Vec::new()) };
+
+        assert_eq!(gradient.offset_at(50.0, 50.0), 0.0);
+        assert_eq!(gradient.offset_at(100.0, 50.0), 1.0);
+        assert_eq!(gradient.offset_at(50.0, 62.5), 0.5);
+    }
+}
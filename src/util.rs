@@ -2,12 +2,60 @@
 
 use lazy_static::lazy_static;
 use regex::Regex;
-use serde::Deserialize;
+use crate::error::PrideError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::fmt;
 
 /// size of an element that can be represented as a percentage, a fraction, or a number
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 pub struct PartialSize(pub String);
 
+impl<'de> Deserialize<'de> for PartialSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PartialSizeVisitor;
+
+        // numbers are accepted as well as strings, since they aren't always passed through as strings
+        impl<'de> de::Visitor<'de> for PartialSizeVisitor {
+            type Value = PartialSize;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a percentage, a fraction, or a number")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(PartialSize(value.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(PartialSize(value.to_string()))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                Ok(PartialSize(value.to_string()))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                Ok(PartialSize(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(PartialSizeVisitor)
+    }
+}
+
+// plain numbers are written out as numbers, so they come out the same way they'd usually be written
+impl Serialize for PartialSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Ok(number) = self.0.parse::<u64>() {
+            serializer.serialize_u64(number)
+        } else if let Some(number) = self.0.parse::<f64>().ok().filter(|number| number.is_finite()) {
+            serializer.serialize_f64(number)
+        } else {
+            serializer.serialize_str(&self.0)
+        }
+    }
+}
+
 impl PartialSize {
     /// convert this PartialSize into a floating-point number
     pub fn as_number(&self) -> Option<f64> {
@@ -25,6 +73,20 @@ impl PartialSize {
             None
         }
     }
+
+    /// convert this PartialSize into a floating-point number, saying what it's for if it isn't valid
+    pub fn to_number(&self, what: &'static str) -> Result<f64, PrideError> {
+        self.as_number().ok_or_else(|| PrideError::InvalidNumber { what, value: self.0.clone() })
+    }
+}
+
+/// joins items into a list of quoted names, like `"a", "b" or "c"`
+pub fn quoted_list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => format!("\"{}\"", item),
+        [rest @ .., last] => format!("{} or \"{}\"", rest.iter().map(|item| format!("\"{}\"", item)).collect::<Vec<_>>().join(", "), last),
+    }
 }
 
 /// parse float value from percentage string